@@ -0,0 +1,39 @@
+// a small criterion-style harness without the dependency, warms up until a batch of iterations
+// takes a millisecond, then times a set of batches and reports the median per iteration
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const WARMUP: Duration = Duration::from_millis(300);
+const BATCH_TIME: Duration = Duration::from_millis(1);
+const SAMPLES: usize = 100;
+
+fn time_batch<T>(f: &mut impl FnMut() -> T, batch: u64) -> Duration {
+    let start = Instant::now();
+    for _ in 0..batch {
+        black_box(f());
+    }
+    start.elapsed()
+}
+
+pub fn bench<T>(name: &str, mut f: impl FnMut() -> T) {
+    let mut batch = 1;
+    let warmup_end = Instant::now() + WARMUP;
+    while Instant::now() < warmup_end {
+        if time_batch(&mut f, batch) < BATCH_TIME {
+            batch *= 2;
+        }
+    }
+
+    let mut samples: Vec<f64> = (0..SAMPLES)
+        .map(|_| time_batch(&mut f, batch).as_nanos() as f64 / batch as f64)
+        .collect();
+    samples.sort_by(f64::total_cmp);
+    println!(
+        "{name:<28} {:>10.1} ns/iter  [{:.1} .. {:.1}]",
+        samples[SAMPLES / 2],
+        samples[SAMPLES / 20],
+        samples[SAMPLES - SAMPLES / 20 - 1]
+    );
+}
@@ -0,0 +1,66 @@
+// Position::material, which goes off the piece bitboards, against scanning every square of the
+// mailbox, run with cargo bench --bench material
+mod common;
+
+use std::hint::black_box;
+
+use ctenophore::{
+    board::{Board, Position, STARTPOS_SFEN},
+    eval::PIECE_VALUES,
+    types::{
+        piece::Piece,
+        side::Side,
+        square::{Square, NUM_SQUARES},
+    },
+};
+
+const POSITIONS: [&str; 4] = [
+    STARTPOS_SFEN,
+    "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+    "8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124",
+    "4k4/9/9/9/9/9/9/9/4K4 b 2R2B4G4S4N4L18P 1",
+];
+
+fn mailbox_material(board: &Board, side: Side) -> i32 {
+    let state = board.current_position();
+    let on_board: i32 = (0..NUM_SQUARES)
+        .map(|sq| state.piece_on_square(Square(sq)))
+        .filter(|piece| *piece != Piece::NONE && piece.side() == side.as_u8())
+        .map(|piece| PIECE_VALUES[piece.piece().as_usize()])
+        .sum();
+    let in_hand: i32 = board
+        .hand(side)
+        .into_iter()
+        .map(|(piece, count)| PIECE_VALUES[piece.as_usize()] * i32::from(count))
+        .sum();
+    on_board + in_hand
+}
+
+fn balance(position: &Position) -> i32 {
+    position.material(Side::Sente) - position.material(Side::Gote)
+}
+
+fn main() {
+    let boards: Vec<Board> = POSITIONS
+        .iter()
+        .map(|sfen| {
+            let mut board = Board::default();
+            board.load_fen(sfen);
+            board
+        })
+        .collect();
+    let boards = black_box(&boards);
+
+    common::bench("material/popcount", || {
+        boards
+            .iter()
+            .map(|board| balance(board.current_position()))
+            .sum::<i32>()
+    });
+    common::bench("material/mailbox", || {
+        boards
+            .iter()
+            .map(|board| mailbox_material(board, Side::Sente) - mailbox_material(board, Side::Gote))
+            .sum::<i32>()
+    });
+}
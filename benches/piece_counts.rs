@@ -0,0 +1,55 @@
+// Position::piece_counts in one pass over the piece bitboards, against a popcount of sided_piece
+// for every type and side the way material counts them, run with cargo bench --bench piece_counts
+mod common;
+
+use std::hint::black_box;
+
+use ctenophore::{
+    board::{Board, Position, STARTPOS_SFEN},
+    types::{piece::NUM_PIECE_TYPES, side::Side},
+};
+
+const POSITIONS: [&str; 3] = [
+    STARTPOS_SFEN,
+    "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+    "8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124",
+];
+
+fn per_type_counts(position: &Position) -> [u32; NUM_PIECE_TYPES as usize] {
+    let mut counts = [0; NUM_PIECE_TYPES as usize];
+    for (piece, count) in counts.iter_mut().enumerate() {
+        for side in Side::ALL {
+            *count += position.sided_piece(piece as u8, side).popcount();
+        }
+    }
+    counts
+}
+
+fn main() {
+    let boards: Vec<Board> = POSITIONS
+        .iter()
+        .map(|sfen| {
+            let mut board = Board::default();
+            board.load_fen(sfen);
+            board
+        })
+        .collect();
+    let boards = black_box(&boards);
+
+    common::bench("piece_counts/one pass", || {
+        for board in boards {
+            black_box(board.current_position().piece_counts());
+        }
+    });
+    common::bench("piece_counts/per type", || {
+        for board in boards {
+            black_box(per_type_counts(board.current_position()));
+        }
+    });
+    common::bench("occupied/popcount", || {
+        boards
+            .iter()
+            .map(|board| board.current_position().occupied().popcount())
+            .sum::<u32>()
+    });
+}
@@ -1,26 +1,72 @@
+use std::cmp::Reverse;
+
 use crate::{
+    eval::PIECE_VALUES,
     movegen::{
-        get_bishop_attacks, get_gold_attacks, get_king_attacks, get_knight_attacks,
-        get_lance_attacks, get_rook_attacks, get_silver_attacks, setwise_pawns,
+        between, get_bishop_attacks, get_gold_attacks, get_king_attacks, get_knight_attacks,
+        get_lance_attacks, get_rook_attacks, get_silver_attacks, line, setwise_pawns,
     },
     types::{
         action::{Action, Actionlist},
         bitboard::Bitboard,
         hand::Hand,
         piece::{Piece, NUM_PIECE_TYPES},
-        square::{Square, NUM_SQUARES},
+        side::Side,
+        square::{Square, BOARD_LEN, NUM_SQUARES},
     },
+    zobrist::ZOBRIST,
 };
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     sides: [Bitboard; 2],
     pieces: [Bitboard; NUM_PIECE_TYPES as usize],
+    #[cfg_attr(feature = "serde", serde(with = "mailbox_serde"))]
     mailbox: [Piece; NUM_SQUARES as usize],
     hands: [Hand; 2],
     checkers: Bitboard,
+    hash: u64,
+    // moves since the last capture, pawn move or pawn drop, kept here so unmaking restores it
+    halfmove_clock: u16,
+}
+
+// serde only goes up to 32 long arrays, so the mailbox goes through a sequence
+#[cfg(feature = "serde")]
+mod mailbox_serde {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::types::{piece::Piece, square::NUM_SQUARES};
+
+    pub fn serialize<S: Serializer>(
+        mailbox: &[Piece; NUM_SQUARES as usize],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(mailbox)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[Piece; NUM_SQUARES as usize], D::Error> {
+        let squares = Vec::<Piece>::deserialize(deserializer)?;
+        squares
+            .try_into()
+            .map_err(|squares: Vec<Piece>| D::Error::invalid_length(squares.len(), &"81 squares"))
+    }
 }
 
+// only the pieces and hands, the hash and checkers are derived from them and the clock is history
+impl PartialEq for Position {
+    fn eq(&self, other: &Self) -> bool {
+        self.sides == other.sides
+            && self.pieces == other.pieces
+            && self.mailbox == other.mailbox
+            && self.hands == other.hands
+    }
+}
+
+impl Eq for Position {}
+
 impl Default for Position {
     fn default() -> Self {
         Self {
@@ -29,6 +75,8 @@ impl Default for Position {
             mailbox: [Piece::default(); NUM_SQUARES as usize],
             hands: [Hand::default(); 2],
             checkers: Bitboard::EMPTY,
+            hash: 0,
+            halfmove_clock: 0,
         }
     }
 }
@@ -39,6 +87,7 @@ impl Position {
         self.sides[piece.side() as usize] ^= bitboard_square;
         self.pieces[piece.piece().as_usize()] ^= bitboard_square;
         self.mailbox[sq.as_usize()] = piece;
+        self.hash ^= ZOBRIST.piece(piece, sq);
     }
 
     pub fn remove_piece(&mut self, sq: Square, piece: Piece) {
@@ -46,6 +95,35 @@ impl Position {
         self.sides[piece.side() as usize] ^= bitboard_square;
         self.pieces[piece.piece().as_usize()] ^= bitboard_square;
         self.mailbox[sq.as_usize()] = Piece::NONE;
+        self.hash ^= ZOBRIST.piece(piece, sq);
+    }
+
+    pub fn add_to_hand(&mut self, side: Side, piece: Piece) {
+        let piece = piece.piece().unpromote();
+        let count = self.hands[side.as_usize()].num(piece);
+        self.hash ^= ZOBRIST.hand(side.as_u8(), piece, count);
+        self.hands[side.as_usize()].add(piece);
+    }
+
+    pub fn remove_from_hand(&mut self, side: Side, piece: Piece) {
+        let piece = piece.piece().unpromote();
+        self.hands[side.as_usize()].remove(piece);
+        let count = self.hands[side.as_usize()].num(piece);
+        self.hash ^= ZOBRIST.hand(side.as_u8(), piece, count);
+    }
+
+    // goes one piece at a time to keep the hash in sync, an overfull slot would spill into the next
+    pub fn set_hand(&mut self, side: Side, piece: Piece, count: u32) {
+        assert!(
+            count <= Hand::max_count(piece),
+            "too many of {piece} for a hand"
+        );
+        while u32::from(self.hands[side.as_usize()].num(piece)) > count {
+            self.remove_from_hand(side, piece);
+        }
+        while u32::from(self.hands[side.as_usize()].num(piece)) < count {
+            self.add_to_hand(side, piece);
+        }
     }
 
     pub fn move_piece(&mut self, from: Square, piece: Piece, to: Square, victim: Piece) {
@@ -67,24 +145,343 @@ impl Position {
     }
 
     #[must_use]
-    pub fn sided_piece(&self, piece: u8, side: u8) -> Bitboard {
-        self.sides[side as usize] & self.pieces[piece as usize]
+    pub fn sided_piece(&self, piece: u8, side: Side) -> Bitboard {
+        self.sides[side.as_usize()] & self.pieces[piece as usize]
+    }
+
+    // how many of each piece type are on the board for both sides together, indexed by piece type,
+    // eval doesn't use it since material needs each side's counts, which are a popcount of
+    // sided_piece away. occupancy isn't cached, it's one or of the two side bitboards where a cache
+    // would be a third bitboard to keep in step through every make and unmake
+    #[must_use]
+    pub fn piece_counts(&self) -> [u32; NUM_PIECE_TYPES as usize] {
+        self.pieces.map(|bb| bb.popcount())
+    }
+
+    // two of a side's unpromoted pawns on one file, tokins don't count
+    #[must_use]
+    pub fn has_nifu(&self, side: Side) -> bool {
+        let pawns = self.sided_piece(Piece::PAWN.raw(), side);
+        (0..BOARD_LEN).any(|file| (pawns & Bitboard::from_file(file)).contains_multiple())
+    }
+
+    // a side's material on the board and in hand, counted off the piece bitboards
+    #[must_use]
+    pub fn material(&self, side: Side) -> i32 {
+        let board: i32 = (0..NUM_PIECE_TYPES)
+            .map(|piece| {
+                PIECE_VALUES[piece as usize] * self.sided_piece(piece, side).popcount() as i32
+            })
+            .sum();
+        let hand: i32 = self.hands[side.as_usize()]
+            .into_iter()
+            .map(|(piece, count)| PIECE_VALUES[piece.as_usize()] * i32::from(count))
+            .sum();
+        board + hand
+    }
+
+    // checks the bitboards and the mailbox agree with each other, for debugging
+    pub fn debug_validate(&self) -> Result<(), String> {
+        let occ = self.occupied();
+        if (self.sides[0] & self.sides[1]).is_not_empty() {
+            return Err("sides overlap".to_owned());
+        }
+        if (occ & !Bitboard::FULL).is_not_empty() {
+            return Err("bits set off the board".to_owned());
+        }
+        let all_pieces = self
+            .pieces
+            .iter()
+            .fold(Bitboard::EMPTY, |acc, bb| acc | *bb);
+        if all_pieces != occ {
+            return Err("piece bitboards don't match the sides".to_owned());
+        }
+        let piece_count: u32 = self.piece_counts().iter().sum();
+        if piece_count != occ.popcount() {
+            return Err("piece bitboards overlap".to_owned());
+        }
+        for sq in 0..NUM_SQUARES {
+            let sq = Square(sq);
+            let piece = self.piece_on_square(sq);
+            if piece == Piece::NONE {
+                if occ.contains(sq) {
+                    return Err(format!("{sq} is occupied but empty in the mailbox"));
+                }
+            } else if !(self.pieces[piece.piece().as_usize()] & self.sides[piece.side() as usize])
+                .contains(sq)
+            {
+                return Err(format!(
+                    "mailbox has {piece} on {sq} but the bitboards don't"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // each of a side's pieces along with the square it's on
+    pub fn pieces_of(&self, side: Side) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.sides[side.as_usize()]
+            .into_iter()
+            .map(|sq| (sq, self.piece_on_square(sq)))
+    }
+
+    #[must_use]
+    pub fn king_square(&self, side: Side) -> Square {
+        self.sided_piece(Piece::KING.raw(), side).lsb_square()
+    }
+
+    // tsume and other partial positions can be missing a king
+    #[must_use]
+    pub fn try_king_square(&self, side: Side) -> Option<Square> {
+        self.sided_piece(Piece::KING.raw(), side).first_square()
+    }
+
+    // pieces of `side` that can't leave the line between their king and an enemy slider
+    #[must_use]
+    pub fn pinned(&self, side: Side) -> Bitboard {
+        let Some(king_sq) = self.try_king_square(side) else {
+            return Bitboard::EMPTY;
+        };
+        let opps = side.flip();
+        let occ = self.occupied();
+
+        let orthogonal = self.sided_piece(Piece::ROOK.raw(), opps)
+            | self.sided_piece(Piece::PROMO_ROOK.raw(), opps);
+        let diagonal = self.sided_piece(Piece::BISHOP.raw(), opps)
+            | self.sided_piece(Piece::PROMO_BISHOP.raw(), opps);
+        // lances only pin along the file they attack down
+        let snipers = (get_rook_attacks(king_sq, Bitboard::EMPTY) & orthogonal)
+            | (get_bishop_attacks(king_sq, Bitboard::EMPTY) & diagonal)
+            | (get_lance_attacks(king_sq, Bitboard::EMPTY, side.as_u8())
+                & self.sided_piece(Piece::LANCE.raw(), opps));
+
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers {
+            let blockers = between(king_sq, sniper) & occ;
+            if blockers.contains_one() {
+                pinned |= blockers & self.sides[side.as_usize()];
+            }
+        }
+        pinned
+    }
+
+    // pieces of `side` attacking a square, found by generating attacks from the square as the other side
+    #[must_use]
+    pub fn attackers_to(&self, sq: Square, side: Side, occ: Bitboard) -> Bitboard {
+        let opps = side.flip();
+        // a pawn attacks us from where our own pawn would push to
+        let pawn_atk_bb = setwise_pawns(Bitboard::from_square(sq), opps.as_u8());
+        let gold_movers = self.sided_piece(Piece::GOLD.raw(), side)
+            | self.sided_piece(Piece::PROMO_PAWN.raw(), side)
+            | self.sided_piece(Piece::PROMO_LANCE.raw(), side)
+            | self.sided_piece(Piece::PROMO_KNIGHT.raw(), side)
+            | self.sided_piece(Piece::PROMO_SILVER.raw(), side);
+        (pawn_atk_bb & self.sided_piece(Piece::PAWN.raw(), side))
+            | (get_lance_attacks(sq, occ, opps.as_u8())
+                & self.sided_piece(Piece::LANCE.raw(), side))
+            | (get_knight_attacks(sq, opps.as_u8()) & self.sided_piece(Piece::KNIGHT.raw(), side))
+            | (get_silver_attacks(sq, opps.as_u8()) & self.sided_piece(Piece::SILVER.raw(), side))
+            | (get_bishop_attacks(sq, occ)
+                & (self.sided_piece(Piece::BISHOP.raw(), side)
+                    | self.sided_piece(Piece::PROMO_BISHOP.raw(), side)))
+            | (get_rook_attacks(sq, occ)
+                & (self.sided_piece(Piece::ROOK.raw(), side)
+                    | self.sided_piece(Piece::PROMO_ROOK.raw(), side)))
+            | (get_king_attacks(sq)
+                & (self.sided_piece(Piece::KING.raw(), side)
+                    | self.sided_piece(Piece::PROMO_BISHOP.raw(), side)
+                    | self.sided_piece(Piece::PROMO_ROOK.raw(), side)))
+            | (get_gold_attacks(sq, opps.as_u8()) & gold_movers)
+    }
+}
+
+// bigger than the most valuable piece, see mvv_lva
+const MVV_LVA_VICTIM_SCALE: i32 = 2048;
+
+pub const STARTPOS_SFEN: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1";
+
+// why an sfen couldn't be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    // fewer than the three required tokens
+    MissingToken,
+    InvalidCharacter(char),
+    // the move number wasn't a non-negative number
+    BadPly(String),
+    // a rank describing more than 9 files
+    FileOverflow,
+    TooManyRanks,
+    // a rank or the whole board stopping short of 9 files or ranks
+    MissingSquares,
+    // a side with no king or more than one, the attacker can go without one in tsume mode
+    IllegalKingCount(Side),
+    // two unpromoted pawns of one side on a file
+    Nifu(Side),
+    // more of a piece in hand than a hand can hold
+    HandOverflow(char),
+    // a pawn or lance on the last rank or a knight on the last two, none of them could ever move
+    StuckPiece(Square),
+}
+
+// the hand slot a piece is kept in, kings and empty squares never go in a hand so they come back
+// as the character an sfen hand would have had for them
+fn hand_slot(side: Side, piece: Piece) -> Result<Piece, FenError> {
+    let piece = piece.piece().min(Piece::NONE);
+    if piece == Piece::KING || piece == Piece::NONE {
+        let c = piece.as_stm(side.as_u8()).to_string().remove(0);
+        return Err(FenError::InvalidCharacter(c));
+    }
+    Ok(piece.unpromote())
+}
+
+// makes sure the board token lays out exactly 9 ranks of 9 files before anything is placed,
+// runs of digits are read as one number so "10" is an overflow rather than a 1 and a 0
+fn check_board_shape(board: &str) -> Result<(), FenError> {
+    let mut rank_count = 0;
+    for rank in board.split('/') {
+        rank_count += 1;
+        if rank_count > BOARD_LEN {
+            return Err(FenError::TooManyRanks);
+        }
+        let mut files = 0;
+        let mut empty_run = 0;
+        for c in rank.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                empty_run = empty_run * 10 + digit;
+                if empty_run > u32::from(BOARD_LEN) {
+                    return Err(FenError::FileOverflow);
+                }
+                continue;
+            }
+            files += empty_run;
+            empty_run = 0;
+            if c != '+' {
+                files += 1;
+            }
+            if files > u32::from(BOARD_LEN) {
+                return Err(FenError::FileOverflow);
+            }
+        }
+        files += empty_run;
+        if files > u32::from(BOARD_LEN) {
+            return Err(FenError::FileOverflow);
+        }
+        if files < u32::from(BOARD_LEN) {
+            return Err(FenError::MissingSquares);
+        }
+    }
+    if rank_count < BOARD_LEN {
+        return Err(FenError::MissingSquares);
+    }
+    Ok(())
+}
+
+// how a game ended, from the perspective of the side to move where it matters
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameResult {
+    // no legal moves while in check
+    Checkmate,
+    // no legal moves without being in check, still a loss in shogi
+    Stalemate,
+    // sennichite, a draw
+    Repetition,
+    // sennichite where one side checked the whole way, a loss for that side
+    PerpetualCheckLoss,
+    // the side to move wins under the 27-point rule
+    Impasse,
+    // the game ran past the ply cap, a draw
+    MaxPly,
+}
+
+// attackers in the order see tries them, cheapest first and the king last
+const SEE_ORDER: [Piece; 14] = [
+    Piece::PAWN,
+    Piece::LANCE,
+    Piece::KNIGHT,
+    Piece::SILVER,
+    Piece::PROMO_PAWN,
+    Piece::PROMO_LANCE,
+    Piece::GOLD,
+    Piece::PROMO_KNIGHT,
+    Piece::PROMO_SILVER,
+    Piece::BISHOP,
+    Piece::ROOK,
+    Piece::PROMO_BISHOP,
+    Piece::PROMO_ROOK,
+    Piece::KING,
+];
+
+// the king can't actually be traded, so taking it is worth more than anything else on the board
+const fn see_value(piece: Piece) -> i32 {
+    if piece.raw() == Piece::KING.raw() {
+        10000
+    } else {
+        PIECE_VALUES[piece.as_usize()]
+    }
+}
+
+// the squares a piece on sq attacks for its own side, pawns included though movegen does them setwise
+fn piece_attacks(piece: Piece, sq: Square, occ: Bitboard) -> Bitboard {
+    let side = piece.side();
+    match piece.piece() {
+        Piece::PAWN => setwise_pawns(Bitboard::from_square(sq), side),
+        Piece::LANCE => get_lance_attacks(sq, occ, side),
+        Piece::KNIGHT => get_knight_attacks(sq, side),
+        Piece::SILVER => get_silver_attacks(sq, side),
+        Piece::BISHOP => get_bishop_attacks(sq, occ),
+        Piece::ROOK => get_rook_attacks(sq, occ),
+        Piece::GOLD
+        | Piece::PROMO_PAWN
+        | Piece::PROMO_LANCE
+        | Piece::PROMO_KNIGHT
+        | Piece::PROMO_SILVER => get_gold_attacks(sq, side),
+        Piece::KING => get_king_attacks(sq),
+        // the king steps overlapping the slider rays are already in them, so the union only
+        // adds the 4 orthogonal steps for a horse and the 4 diagonal steps for a dragon
+        Piece::PROMO_BISHOP => get_bishop_attacks(sq, occ) | get_king_attacks(sq),
+        Piece::PROMO_ROOK => get_rook_attacks(sq, occ) | get_king_attacks(sq),
+        _ => panic!("invalid piece"),
     }
 }
 
+// one king a side, except the side to move in tsume mode can go without, and no nifu
+fn check_kings_and_nifu(state: &Position, stm: Side, tsume_mode: bool) -> Result<(), FenError> {
+    for side in Side::ALL {
+        let kings = state.sided_piece(Piece::KING.raw(), side).popcount();
+        let kingless_allowed = tsume_mode && side == stm;
+        if kings > 1 || (kings == 0 && !kingless_allowed) {
+            return Err(FenError::IllegalKingCount(side));
+        }
+        if state.has_nifu(side) {
+            return Err(FenError::Nifu(side));
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Board {
     states: Vec<Position>,
-    stm: u8,
+    stm: Side,
+    // the sfen move number, half-moves counted from 1
     ply: i16,
+    // lets the side to move load without a king, for tsume problems
+    tsume_mode: bool,
+    // games running past this ply are drawn, keeps self-play from going on forever
+    max_ply: i16,
 }
 
 impl Default for Board {
     fn default() -> Self {
+        let mut states = Vec::with_capacity(256);
+        states.push(Position::default());
         Self {
-            states: vec![Position::default(); 256],
-            stm: 0,
-            ply: 0,
+            states,
+            stm: Side::Sente,
+            ply: 1,
+            tsume_mode: false,
+            max_ply: 512,
         }
     }
 }
@@ -94,12 +491,16 @@ impl Board {
         self.states.last().expect("No current state")
     }
 
-    #[allow(dead_code)]
     fn current_state_mut(&mut self) -> &mut Position {
         self.states.last_mut().expect("No current state")
     }
 
     pub fn print_state(&self) {
+        self.print_state_for(Side::Sente);
+    }
+
+    // the board as seen from a side, so for gote it's turned around and their pieces are at the bottom
+    pub fn print_state_for(&self, side: Side) {
         let state = self.current_state();
 
         // top line
@@ -109,17 +510,20 @@ impl Board {
         }
         println!("───┐");
 
-        for i in (0..9).rev() {
-            for j in 0..9 {
-                if state.mailbox[i * 9 + j].to_string().len() == 2 {
-                    print!("│{} ", state.mailbox[i * 9 + j]);
+        for rank in (0..9).rev() {
+            for file in 0..9 {
+                let sq = Square::from_file_rank(file, rank);
+                let sq = if side == Side::Sente { sq } else { sq.flip() };
+                let piece = state.piece_on_square(sq);
+                if piece.to_string().len() == 2 {
+                    print!("│{} ", piece);
                 } else {
-                    print!("│ {} ", state.mailbox[i * 9 + j]);
+                    print!("│ {} ", piece);
                 }
             }
             println!("│");
             // line
-            if i != 0 {
+            if rank != 0 {
                 print!("├");
                 for _k in 0..8 {
                     print!("───┼")
@@ -137,492 +541,2416 @@ impl Board {
 
         println!();
 
-        println!("stm: {}", if self.stm == 0 { "sente" } else { "gote" });
+        println!("stm: {}", self.stm);
         println!("sente hand: {}", state.hands[0]);
-        println!(
-            "gote hand: {}",
-            state.hands[1].to_string().to_ascii_lowercase()
-        );
+        println!("gote hand: {}", state.hands[1].display_for(Side::Gote));
         println!("ply count: {}", self.ply);
     }
 
+    pub fn set_startpos(&mut self) {
+        self.load_fen(STARTPOS_SFEN);
+    }
+
+    // panics on anything malformed, see try_load_fen for the checked version
     pub fn load_fen(&mut self, fen: &str) {
+        if let Err(err) = self.try_load_fen(fen) {
+            panic!("invalid sfen \"{fen}\": {err:?}");
+        }
+    }
+
+    // leaves the board untouched if the sfen is malformed
+    pub fn try_load_fen(&mut self, fen: &str) -> Result<(), FenError> {
         let mut state = Position::default();
 
         let mut fen_segments = fen.split_ascii_whitespace();
 
         // first token: position
-        let mut token = fen_segments.next().expect("no position?");
+        let mut token = fen_segments.next().ok_or(FenError::MissingToken)?;
+        check_board_shape(token)?;
         let mut ranks = token.rsplit('/');
         let mut i: Square = Square(0);
         for rank in ranks.by_ref() {
             let mut is_promoted = false;
             for c in rank.chars() {
-                match c {
-                    '+' => {
-                        // promote next piece
-                        is_promoted = true;
-                    }
-                    'p' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::PAWN.raw() + (8 * is_promoted as u8),
-                                Piece::GOTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'P' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::PAWN.raw() + (8 * is_promoted as u8),
-                                Piece::SENTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'l' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::LANCE.raw() + (8 * is_promoted as u8),
-                                Piece::GOTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'L' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::LANCE.raw() + (8 * is_promoted as u8),
-                                Piece::SENTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'n' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::KNIGHT.raw() + (8 * is_promoted as u8),
-                                Piece::GOTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'N' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::KNIGHT.raw() + (8 * is_promoted as u8),
-                                Piece::SENTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    's' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::SILVER.raw() + (8 * is_promoted as u8),
-                                Piece::GOTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'S' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::SILVER.raw() + (8 * is_promoted as u8),
-                                Piece::SENTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'g' => {
-                        debug_assert!(!is_promoted);
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(Piece::GOLD.raw(), Piece::GOTE.raw()),
-                        );
-                        i += Square(1);
-                    }
-                    'G' => {
-                        debug_assert!(!is_promoted);
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(Piece::GOLD.raw(), Piece::SENTE.raw()),
-                        );
-                        i += Square(1);
-                    }
-                    'b' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::BISHOP.raw() + (8 * is_promoted as u8),
-                                Piece::GOTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
+                if c == '+' {
+                    // promote next piece, which has to be there and be one that can promote
+                    if is_promoted {
+                        return Err(FenError::InvalidCharacter(c));
                     }
-                    'B' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::BISHOP.raw() + (8 * is_promoted as u8),
-                                Piece::SENTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'r' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::ROOK.raw() + (8 * is_promoted as u8),
-                                Piece::GOTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'R' => {
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(
-                                Piece::ROOK.raw() + (8 * is_promoted as u8),
-                                Piece::SENTE.raw(),
-                            ),
-                        );
-                        is_promoted = false;
-                        i += Square(1);
-                    }
-                    'k' => {
-                        debug_assert!(!is_promoted);
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(Piece::KING.raw(), Piece::GOTE.raw()),
-                        );
-                        i += Square(1);
-                    }
-                    'K' => {
-                        debug_assert!(!is_promoted);
-                        state.add_piece(
-                            i,
-                            Piece::new_unchecked(Piece::KING.raw(), Piece::SENTE.raw()),
-                        );
-                        i += Square(1);
-                    }
-                    _ => {
-                        i += Square(
-                            c.to_digit(10)
-                                .unwrap_or_else(|| panic!("invalid character in fen: {c}"))
-                                as u8,
-                        )
+                    is_promoted = true;
+                    continue;
+                }
+                if let Some(empty) = c.to_digit(10) {
+                    if is_promoted {
+                        return Err(FenError::InvalidCharacter('+'));
                     }
+                    i += Square(empty as u8);
+                    continue;
                 }
+                let piece = match c.to_ascii_uppercase() {
+                    'P' => Piece::PAWN,
+                    'L' => Piece::LANCE,
+                    'N' => Piece::KNIGHT,
+                    'S' => Piece::SILVER,
+                    'G' => Piece::GOLD,
+                    'B' => Piece::BISHOP,
+                    'R' => Piece::ROOK,
+                    'K' => Piece::KING,
+                    _ => return Err(FenError::InvalidCharacter(c)),
+                };
+                let piece = if !is_promoted {
+                    piece
+                } else if piece < Piece::GOLD {
+                    piece.promote()
+                } else {
+                    return Err(FenError::InvalidCharacter('+'));
+                };
+                let side = if c.is_ascii_uppercase() {
+                    Piece::SENTE
+                } else {
+                    Piece::GOTE
+                };
+                state.add_piece(i, Piece::new_unchecked(piece.raw(), side.raw()));
+                is_promoted = false;
+                i += Square(1);
+            }
+            if is_promoted {
+                return Err(FenError::InvalidCharacter('+'));
             }
         }
 
         // second token: stm
-        token = fen_segments.next().expect("no ctm?");
-        self.stm = u8::from(token == "w");
+        token = fen_segments.next().ok_or(FenError::MissingToken)?;
+        let stm = if token == "w" {
+            Side::Gote
+        } else {
+            Side::Sente
+        };
+        if stm == Side::Gote {
+            state.hash ^= ZOBRIST.stm();
+        }
 
         // third token: hand
-        token = fen_segments.next().expect("no hand");
+        token = fen_segments.next().ok_or(FenError::MissingToken)?;
         if token != "-" {
-            let mut count = 1;
+            // a count can run to more than one digit like 10P, no count means one
+            let mut count = None;
             for c in token.chars() {
-                match c {
-                    'p' => {
-                        state.hands[1].set(Piece::PAWN, count);
-                        count = 1;
-                    }
-                    'P' => {
-                        state.hands[0].set(Piece::PAWN, count);
-                        count = 1;
-                    }
-                    'l' => {
-                        state.hands[1].set(Piece::LANCE, count);
-                        count = 1;
-                    }
-                    'L' => {
-                        state.hands[0].set(Piece::LANCE, count);
-                        count = 1;
-                    }
-                    'n' => {
-                        state.hands[1].set(Piece::KNIGHT, count);
-                        count = 1;
-                    }
-                    'N' => {
-                        state.hands[0].set(Piece::KNIGHT, count);
-                        count = 1;
-                    }
-                    's' => {
-                        state.hands[1].set(Piece::SILVER, count);
-                        count = 1;
-                    }
-                    'S' => {
-                        state.hands[0].set(Piece::SILVER, count);
-                        count = 1;
-                    }
-                    'g' => {
-                        state.hands[1].set(Piece::GOLD, count);
-                        count = 1;
-                    }
-                    'G' => {
-                        state.hands[0].set(Piece::GOLD, count);
-                        count = 1;
-                    }
-                    'b' => {
-                        state.hands[1].set(Piece::BISHOP, count);
-                        count = 1;
-                    }
-                    'B' => {
-                        state.hands[0].set(Piece::BISHOP, count);
-                        count = 1;
-                    }
-                    'r' => {
-                        state.hands[1].set(Piece::ROOK, count);
-                        count = 1;
-                    }
-                    'R' => {
-                        state.hands[0].set(Piece::ROOK, count);
-                        count = 1;
-                    }
-                    // sets the count to use for next time
-                    _ => {
-                        count = c
-                            .to_digit(10)
-                            .unwrap_or_else(|| panic!("invalid character in fen: {c}"))
-                    }
+                if let Some(digit) = c.to_digit(10) {
+                    count = Some(count.unwrap_or(0) * 10 + digit);
+                    continue;
+                }
+                let piece = match c.to_ascii_uppercase() {
+                    'P' => Piece::PAWN,
+                    'L' => Piece::LANCE,
+                    'N' => Piece::KNIGHT,
+                    'S' => Piece::SILVER,
+                    'G' => Piece::GOLD,
+                    'B' => Piece::BISHOP,
+                    'R' => Piece::ROOK,
+                    _ => return Err(FenError::InvalidCharacter(c)),
+                };
+                let side = if c.is_ascii_uppercase() {
+                    Side::Sente
+                } else {
+                    Side::Gote
+                };
+                let count = count.take().unwrap_or(1);
+                if count > Hand::max_count(piece) {
+                    return Err(FenError::HandOverflow(c));
                 }
+                state.set_hand(side, piece, count);
             }
         }
 
-        // fourth token: move count (optional)
-        let token_option = fen_segments.next();
-        if token_option.is_some() {
-            self.ply = token_option.unwrap().parse().unwrap();
+        // fourth token: move number (optional), anything after it is ignored
+        // this is the sfen move number, which counts half-moves starting from 1, and is what ply stores
+        let ply = match fen_segments.next() {
+            Some(token) => match token.parse::<i16>() {
+                Ok(ply) if ply >= 0 => ply,
+                _ => return Err(FenError::BadPly(token.to_owned())),
+            },
+            None => 1,
+        };
+
+        check_kings_and_nifu(&state, stm, self.tsume_mode)?;
+
+        // a new position starts a new history, nothing from the previous one carries over
+        self.states.clear();
+        self.states.push(state);
+        self.stm = stm;
+        self.ply = ply;
+        self.update_checkers();
+        Ok(())
+    }
+
+    // the sfen move number, unlike chess it counts every half-move so it already follows the side
+    // to move, sente moves on odd numbers in a game from the start
+    pub fn move_number(&self) -> u32 {
+        u32::try_from(self.ply).unwrap_or(0)
+    }
+
+    pub fn set_tsume_mode(&mut self, tsume_mode: bool) {
+        self.tsume_mode = tsume_mode;
+    }
+
+    pub fn set_max_ply(&mut self, max_ply: i16) {
+        self.max_ply = max_ply;
+    }
+
+    // position editing, for setting positions up from code, every edit starts a fresh history
+    // from the edited position like loading an sfen does, the hash stays in step as pieces change
+
+    // an empty board with empty hands, sente to move
+    pub fn clear(&mut self) {
+        self.states.clear();
+        self.states.push(Position::default());
+        self.stm = Side::Sente;
+        self.ply = 1;
+        self.update_checkers();
+    }
+
+    // replaces whatever was on the square
+    pub fn set_piece(&mut self, sq: Square, piece: Piece) {
+        self.edit(|state| {
+            let old = state.piece_on_square(sq);
+            if old != Piece::NONE {
+                state.remove_piece(sq, old);
+            }
+            state.add_piece(sq, piece);
+        });
+    }
+
+    pub fn remove_piece(&mut self, sq: Square) {
+        self.edit(|state| {
+            let old = state.piece_on_square(sq);
+            if old != Piece::NONE {
+                state.remove_piece(sq, old);
+            }
+        });
+    }
+
+    // promoted pieces go in as their unpromoted side, kings and more than a hand holds are refused
+    // the same way load_fen would refuse them and leave the board as it was
+    pub fn set_hand(&mut self, side: Side, piece: Piece, count: u32) -> Result<(), FenError> {
+        let piece = hand_slot(side, piece)?;
+        if count > Hand::max_count(piece) {
+            let c = piece.as_stm(side.as_u8()).to_string().remove(0);
+            return Err(FenError::HandOverflow(c));
         }
+        self.edit(|state| state.set_hand(side, piece, count));
+        Ok(())
+    }
 
+    fn edit(&mut self, edit: impl FnOnce(&mut Position)) {
+        let mut state = *self.current_state();
+        edit(&mut state);
+        self.states.clear();
         self.states.push(state);
         self.update_checkers();
     }
+
+    pub fn to_sfen(&self) -> String {
+        let state = self.current_state();
+        let mut sfen = String::new();
+
+        // board, top rank first like load_fen expects
+        for rank in (0..9).rev() {
+            let mut empty = 0;
+            for file in 0..9 {
+                let piece = state.piece_on_square(Square::from_rf(rank, file));
+                if piece == Piece::NONE {
+                    empty += 1;
+                } else {
+                    if empty > 0 {
+                        sfen += &empty.to_string();
+                        empty = 0;
+                    }
+                    sfen += &piece.to_string();
+                }
+            }
+            if empty > 0 {
+                sfen += &empty.to_string();
+            }
+            if rank != 0 {
+                sfen.push('/');
+            }
+        }
+
+        // stm
+        sfen += if self.stm == Side::Sente {
+            " b "
+        } else {
+            " w "
+        };
+
+        // hands, sente first
+        let mut hand = state.hands[0].to_sfen(Side::Sente) + &state.hands[1].to_sfen(Side::Gote);
+        if hand.is_empty() {
+            hand.push('-');
+        }
+        sfen += &hand;
+
+        // move count
+        sfen += &format!(" {}", self.move_number());
+
+        sfen
+    }
+
+    // parses a usi move like 7g7f, 8h2b+ or P*5e in the context of the current position
+    // only gives back a legal move, anything else from a gui or a record is None
+    pub fn action_from_usi(&self, usi: &str) -> Option<Action> {
+        let bytes = usi.as_bytes();
+        let action = if bytes.len() == 4 && bytes[1] == b'*' {
+            let piece = match bytes[0] {
+                b'P' => Piece::PAWN,
+                b'L' => Piece::LANCE,
+                b'N' => Piece::KNIGHT,
+                b'S' => Piece::SILVER,
+                b'G' => Piece::GOLD,
+                b'B' => Piece::BISHOP,
+                b'R' => Piece::ROOK,
+                _ => return None,
+            };
+            let to = Square::from_usi(usi.get(2..4)?)?;
+            Action::new_drop(piece.as_stm(self.stm.as_u8()), to)
+        } else if bytes.len() == 4 || (bytes.len() == 5 && bytes[4] == b'+') {
+            let from = Square::from_usi(usi.get(0..2)?)?;
+            let to = Square::from_usi(usi.get(2..4)?)?;
+            Action::new_move(from, to, bytes.len() == 5)
+        } else {
+            return None;
+        };
+
+        // movegen decides what the pieces can do, then the same checks as get_legal_actions
+        let legal = self.get_actions().contains(&action)
+            && self.is_legal(action)
+            && !self.is_pawn_drop_mate(action);
+        legal.then_some(action)
+    }
+
     pub fn get_actions(&self) -> Actionlist {
+        let mut actions = Actionlist::new();
+        self.append_actions(&mut actions);
+        actions
+    }
+
+    // get_actions into a list the caller keeps around, anything already in it is cleared first
+    pub fn append_actions(&self, out: &mut Actionlist) {
+        out.clear();
+        self.generate_actions(Bitboard::FULL, Bitboard::FULL, out);
+    }
+
+    // only the moves that could get us out of check, king moves, capturing a lone checker and
+    // blocking a lone slider, in double check only the king can move
+    fn get_evasions(&self) -> Actionlist {
+        let state = self.current_state();
+        let checkers = state.checkers;
+        let mut actions = Actionlist::new();
+        if checkers.contains_multiple() {
+            self.generate_actions(Bitboard::EMPTY, Bitboard::EMPTY, &mut actions);
+            return actions;
+        }
+
+        let king_sq = state.king_square(self.stm);
+        let checker_sq = checkers.lsb_square();
+        // only sliders can check from further than a step away, and between is empty otherwise
+        let between = between(king_sq, checker_sq);
+        self.generate_actions(checkers | between, between, &mut actions);
+        actions
+    }
+
+    // non-king moves are limited to move_targets and drops to drop_targets, the king can go anywhere
+    fn generate_actions(
+        &self,
+        move_targets: Bitboard,
+        drop_targets: Bitboard,
+        actions: &mut Actionlist,
+    ) {
         let state = self.current_state();
-        let mut actions = Actionlist::default();
         let occ = state.occupied();
-        let us = state.sides[self.stm as usize];
-
-        for sq in us {
-            let piece = state.piece_on_square(sq);
-            let mut attacks = match piece.piece() {
-                Piece::PAWN => Bitboard::EMPTY,
-                Piece::LANCE => get_lance_attacks(sq, occ, self.stm),
-                Piece::KNIGHT => get_knight_attacks(sq, self.stm),
-                Piece::SILVER => get_silver_attacks(sq, self.stm),
-                Piece::BISHOP => get_bishop_attacks(sq, occ),
-                Piece::ROOK => get_rook_attacks(sq, occ),
-                Piece::GOLD
-                | Piece::PROMO_PAWN
-                | Piece::PROMO_LANCE
-                | Piece::PROMO_KNIGHT
-                | Piece::PROMO_SILVER => get_gold_attacks(sq, self.stm),
-                Piece::KING => get_king_attacks(sq),
-                Piece::PROMO_BISHOP => get_bishop_attacks(sq, occ) | get_king_attacks(sq),
-                Piece::PROMO_ROOK => get_rook_attacks(sq, occ) | get_king_attacks(sq),
-                _ => panic!("invalid piece"),
-            };
+        let us = state.sides[self.stm.as_usize()];
+        let zone = Bitboard::promotion_zone(self.stm);
+        let last_rank = Bitboard::last_rank(self.stm);
+        let last_two_ranks = Bitboard::last_two_ranks(self.stm);
+
+        for (sq, piece) in state.pieces_of(self.stm) {
+            // pawns are done setwise below
+            if piece.piece() == Piece::PAWN {
+                continue;
+            }
+            let mut attacks = piece_attacks(piece, sq, occ);
 
             // no taking our own pieces
             attacks &= !us;
+            if piece.piece() != Piece::KING {
+                attacks &= move_targets;
+            }
+
+            // lances on the last rank and knights on the last two have to promote
+            let forced_promotion = match piece.piece() {
+                Piece::LANCE => last_rank,
+                Piece::KNIGHT => last_two_ranks,
+                _ => Bitboard::EMPTY,
+            };
+            let from_zone = zone.contains(sq);
 
-            // parse to actions
+            // parse to actions, only pawn through rook can promote so gold, the king and anything
+            // already promoted never get the option, moving without promoting stays open unless forced
             for bit in attacks {
-                if piece.piece() < Piece::GOLD
-                    && (((self.stm == 0 && bit >= Square(54))
-                        || (self.stm == 1 && bit < Square(27)))
-                        || ((self.stm == 0 && sq >= Square(54))
-                            || (self.stm == 1 && sq < Square(27))))
-                {
+                if piece.piece() < Piece::GOLD && (from_zone || zone.contains(bit)) {
                     actions.push(Action::new_move(sq, bit, true));
                 }
-                if !(piece.piece() == Piece::LANCE
-                    && ((self.stm == 0 && bit >= Square(72)) || (self.stm == 1 && bit < Square(9))))
-                    && !(piece.piece() == Piece::KNIGHT
-                        && ((self.stm == 0 && bit >= Square(63))
-                            || (self.stm == 1 && bit < Square(18))))
-                {
+                if !forced_promotion.contains(bit) {
                     actions.push(Action::new_move(sq, bit, false));
                 }
             }
         }
 
         // setwise pawns
-        let our_pawns = state.sided_piece(Piece::PAWN.as_usize() as u8, self.stm);
-        let mut pawn_attacks = setwise_pawns(our_pawns, self.stm);
+        let our_pawns = state.sided_piece(Piece::PAWN.raw(), self.stm);
+        let mut pawn_attacks = setwise_pawns(our_pawns, self.stm.as_u8());
 
         // no taking our own pieces
-        pawn_attacks &= !us;
+        pawn_attacks &= !us & move_targets;
+
+        // shifting back gives the origins in the same order, so they pair up with their targets
+        let backward = if self.stm == Side::Sente { -9 } else { 9 };
+        let origins = pawn_attacks.shift_signed(backward);
 
         // parse to actions
-        for bit in pawn_attacks {
-            let og = Square((bit.as_u16() as i16 + if self.stm == 0 { -9 } else { 9 }) as u8);
-            if (self.stm == 0 && bit >= Square(54)) || (self.stm == 1 && bit < Square(27)) {
+        for (og, bit) in origins.into_iter().zip(pawn_attacks) {
+            if zone.contains(bit) {
                 actions.push(Action::new_move(og, bit, true));
             }
-            // don't generate pawn promos if it's last row
-            if !((self.stm == 0 && bit >= Square(72)) || (self.stm == 1 && bit < Square(9))) {
+            // promoting is forced on the last rank, a pawn there could never move again
+            if !last_rank.contains(bit) {
                 actions.push(Action::new_move(og, bit, false));
             }
         }
 
-        // drops
-        let hand = state.hands[self.stm as usize];
-        let empty = !occ & Bitboard::FULL;
+        self.generate_drops(drop_targets, actions);
+    }
+
+    // every legal drop, the placement rules come from generate_drops and anything leaving the king
+    // in check or mating with a pawn is filtered out after
+    pub fn get_drops(&self) -> Actionlist {
+        let mut drops = Actionlist::new();
+        self.generate_drops(Bitboard::FULL, &mut drops);
+        drops.retain(|drop| self.is_legal(*drop) && !self.is_pawn_drop_mate(*drop));
+        drops
+    }
+
+    // uchifuzume, for a drop that is_legal already let through, the replies have to be searched so
+    // it gets tried on a copy of the board, at most one square per position can need it
+    fn is_pawn_drop_mate(&self, action: Action) -> bool {
+        if !action.is_drop() || action.piece().piece() != Piece::PAWN || !self.gives_check(action) {
+            return false;
+        }
+        let mut board = self.clone();
+        !board.perform_action(action)
+    }
+
+    fn generate_drops(&self, drop_targets: Bitboard, actions: &mut Actionlist) {
+        let state = self.current_state();
+        let our_pawns = state.sided_piece(Piece::PAWN.raw(), self.stm);
+        let hand = state.hands[self.stm.as_usize()];
+        let empty = !state.occupied() & Bitboard::FULL & drop_targets;
         for (piece, _count) in hand {
             let open_squares = if piece.piece() == Piece::PAWN {
-                // no back ranks, no overlapping files, no checkmates (not handled yet)
-                let free_files = !our_pawns.file_fill() & Bitboard::FULL;
-                let free_squares = if self.stm == 0 {
-                    free_files >> 9
-                } else {
-                    free_files << 9
-                };
-                empty & free_squares
+                // not on the furthest rank, no overlapping files, no checkmates (handled in perform_action)
+                // only our own unpromoted pawns block a file, tokins and enemy pawns don't count for nifu
+                let free_files = !our_pawns.file_fill();
+                empty & free_files & !Bitboard::last_rank(self.stm)
             } else if piece.piece() == Piece::KNIGHT {
-                // no back 2 ranks
-                let free_squares = if self.stm == 0 {
-                    Bitboard::FULL >> 18
-                } else {
-                    Bitboard::FULL << 18
-                };
-                empty & free_squares
+                // not on the two ranks furthest from us where it could never move again, the masks
+                // are per side so for gote that's the bottom of the board
+                empty & !Bitboard::last_two_ranks(self.stm)
             } else if piece.piece() == Piece::LANCE {
-                // no back ranks
-                let free_squares = if self.stm == 0 {
-                    Bitboard::FULL >> 9
-                } else {
-                    Bitboard::FULL << 9
-                };
-                empty & free_squares
+                // not on the furthest rank
+                empty & !Bitboard::last_rank(self.stm)
             } else {
                 empty
             };
 
             for sq in open_squares {
-                actions.push(Action::new_drop(piece.as_stm(self.stm), sq));
+                actions.push(Action::new_drop(piece.as_stm(self.stm.as_u8()), sq));
             }
         }
-
-        actions
     }
-    pub fn piece_on_square(&self, sq: Square) -> Piece {
-        self.current_state().piece_on_square(sq)
+
+    // pseudo-legal captures and promotions, for quiescence
+    pub fn get_captures(&self) -> Actionlist {
+        let them = self.current_state().sides[self.stm.flip().as_usize()];
+        let mut captures = Actionlist::new();
+        for action in &self.get_actions() {
+            if !action.is_drop() && (action.is_promo() || them.contains(action.to())) {
+                captures.push(*action);
+            }
+        }
+        captures
     }
 
-    pub fn get_attackers(&self, sq: Square) -> Bitboard {
-        let opps = 1 - self.stm;
-        let pawn_atk_bb = Bitboard::from_square(Square(
-            (sq.as_u16() as i32 + if self.stm == 0 { 9 } else { -9 }) as u8,
-        ));
-        let state = self.current_state();
-        let occ = state.occupied();
-        let gold_movers = state.sided_piece(Piece::GOLD.raw(), opps)
-            | state.sided_piece(Piece::PROMO_PAWN.raw(), opps)
-            | state.sided_piece(Piece::PROMO_LANCE.raw(), opps)
-            | state.sided_piece(Piece::PROMO_KNIGHT.raw(), opps)
-            | state.sided_piece(Piece::PROMO_SILVER.raw(), opps);
-        (pawn_atk_bb & state.sided_piece(Piece::PAWN.raw(), opps))
-            | (get_lance_attacks(sq, occ, self.stm) & state.sided_piece(Piece::LANCE.raw(), opps))
-            | (get_knight_attacks(sq, self.stm) & state.sided_piece(Piece::KNIGHT.raw(), opps))
-            | (get_silver_attacks(sq, self.stm) & state.sided_piece(Piece::SILVER.raw(), opps))
-            | (get_bishop_attacks(sq, occ)
-                & (state.sided_piece(Piece::BISHOP.raw(), opps)
-                    | state.sided_piece(Piece::PROMO_BISHOP.raw(), opps)))
-            | (get_rook_attacks(sq, occ)
-                & (state.sided_piece(Piece::ROOK.raw(), opps)
-                    | state.sided_piece(Piece::PROMO_ROOK.raw(), opps)))
-            | (get_king_attacks(sq)
-                & (state.sided_piece(Piece::KING.raw(), opps)
-                    | state.sided_piece(Piece::PROMO_BISHOP.raw(), opps)
-                    | state.sided_piece(Piece::PROMO_ROOK.raw(), opps)))
-            | (get_gold_attacks(sq, self.stm) & gold_movers)
+    // pseudo-legal moves that neither capture nor promote, plus every drop, the rest of get_actions
+    pub fn get_quiets(&self) -> Actionlist {
+        let mut quiets = Actionlist::new();
+        for action in &self.get_actions() {
+            if self.is_quiet(*action) {
+                quiets.push(*action);
+            }
+        }
+        quiets
     }
 
-    pub fn in_check(&self) -> bool {
-        !self.current_state().checkers.is_empty()
+    // most valuable victim first, least valuable attacker breaking ties, drops have no victim so go last
+    pub fn sort_captures(&self, actions: &mut Actionlist) {
+        actions.sort_by_key(|action| Reverse(self.mvv_lva(*action)));
     }
 
-    pub fn king_sq(&self) -> Square {
+    // ordering score for captures, higher is better
+    pub fn mvv_lva(&self, action: Action) -> i32 {
+        if action.is_drop() {
+            return i32::MIN;
+        }
         let state = self.current_state();
-        let our_king = state.sided_piece(Piece::KING.raw(), self.stm);
-        Square(our_king.lsb())
+        let victim = self.captured_piece(action);
+        let victim_value = if victim == Piece::NONE {
+            0
+        } else {
+            PIECE_VALUES[victim.piece().as_usize()]
+        };
+        // the victim is scaled past anything an attacker is worth so it always decides first,
+        // the king is worth nothing like in eval so taking with it counts as the cheapest attacker
+        let attacker = state.piece_on_square(action.from()).piece();
+        victim_value * MVV_LVA_VICTIM_SCALE - PIECE_VALUES[attacker.as_usize()]
     }
 
-    pub fn update_checkers(&mut self) {
-        let king_sq = self.king_sq();
-        let king_atkers = self.get_attackers(king_sq);
-        let state = self.current_state_mut();
-        state.checkers = king_atkers;
+    // a move that doesn't capture or promote
+    pub fn is_quiet(&self, action: Action) -> bool {
+        action.is_drop() || (!action.is_promo() && self.captured_piece(action) == Piece::NONE)
     }
 
-    pub fn perform_action(&mut self, action: Action) -> bool {
-        self.states.push(*self.current_state());
-        // just like in anura, not using self.current_state_mut() because of borrowing shenanigans
-        let state = self.states.last_mut().expect("no position");
+    // what an action takes off the board, Piece::NONE for drops and moves to empty squares
+    pub fn captured_piece(&self, action: Action) -> Piece {
         if action.is_drop() {
-            let to = action.to();
-            let piece = action.piece();
-            state.add_piece(to, piece);
-            state.hands[self.stm as usize].dec(piece.unpromote());
+            Piece::NONE
         } else {
-            let from = action.from();
-            let to = action.to();
+            self.piece_on_square(action.to())
+        }
+    }
+
+    // static exchange evaluation, the material swing on the target square if both sides keep
+    // recapturing with their least valuable attacker and stop once it stops paying off
+    pub fn see(&self, action: Action) -> i32 {
+        let state = self.current_state();
+        let to = action.to();
+        let mut occ = state.occupied();
+        // 40 pieces on the board is the most attackers there could be
+        let mut gains = [0; 42];
+        let mut depth = 0;
+
+        let victim = self.captured_piece(action);
+        gains[0] = if victim == Piece::NONE {
+            0
+        } else {
+            see_value(victim.piece())
+        };
+        let mut attacker = if action.is_drop() {
+            action.piece().piece()
+        } else {
+            occ ^= Bitboard::from_square(action.from());
+            state.piece_on_square(action.from()).piece()
+        };
+        let mut side = self.stm.flip();
+
+        loop {
+            depth += 1;
+            // what the other side would net by recapturing
+            gains[depth] = see_value(attacker) - gains[depth - 1];
+
+            // recomputing with the used pieces gone from occ picks up x-rays behind them
+            let attackers = state.attackers_to(to, side, occ) & occ;
+            let Some((piece, sq)) = SEE_ORDER.iter().find_map(|piece| {
+                let bb = attackers & state.sided_piece(piece.raw(), side);
+                bb.is_not_empty().then(|| (*piece, bb.lsb_square()))
+            }) else {
+                break;
+            };
+            occ ^= Bitboard::from_square(sq);
+            attacker = piece;
+            side = side.flip();
+        }
+
+        while depth > 1 {
+            depth -= 1;
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+        }
+        gains[0]
+    }
+
+    // filters get_actions down to the moves that don't leave our king in check
+    pub fn get_legal_actions(&mut self) -> Actionlist {
+        let mut legal_actions = Actionlist::new();
+        self.for_each_legal_action(|action| {
+            legal_actions.push(action);
+            true
+        });
+        legal_actions
+    }
+
+    // the legal moves of the piece on sq, promotions included, picked out before the legality
+    // check so only its moves get checked, board moves are never uchifuzume so the pins are enough
+    pub fn legal_moves_from(&self, sq: Square) -> Actionlist {
+        let actions = if self.in_check() {
+            self.get_evasions()
+        } else {
+            self.get_actions()
+        };
+        let pinned = self.current_state().pinned(self.stm);
+        let mut moves = Actionlist::new();
+        for action in &actions {
+            if action.from_square() == Some(sq) && self.is_legal_with_pins(*action, pinned) {
+                moves.push(*action);
+            }
+        }
+        moves
+    }
+
+    // same filtering as get_legal_actions without collecting the moves
+    pub fn legal_move_count(&mut self) -> usize {
+        let mut count = 0;
+        self.for_each_legal_action(|_action| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    // stops at the first legal move it finds
+    pub fn has_legal_move(&mut self) -> bool {
+        let mut found = false;
+        self.for_each_legal_action(|_action| {
+            found = true;
+            false
+        });
+        found
+    }
+
+    // whether a pseudo-legal action leaves our king safe, worked out from the checkers and pins
+    // without making it, uchifuzume isn't covered since that needs the replies searched
+    pub fn is_legal(&self, action: Action) -> bool {
+        self.is_legal_with_pins(action, self.current_state().pinned(self.stm))
+    }
+
+    fn is_legal_with_pins(&self, action: Action, pinned: Bitboard) -> bool {
+        let state = self.current_state();
+        let Some(king_sq) = state.try_king_square(self.stm) else {
+            return true;
+        };
+
+        // the king can't step onto an attacked square, looking through where it stood for sliders
+        if !action.is_drop() && action.from() == king_sq {
+            let occ = state.occupied() ^ Bitboard::from_square(king_sq);
+            return state
+                .attackers_to(action.to(), self.stm.flip(), occ)
+                .is_empty();
+        }
+
+        // anything else has to take a lone checker or get in its way
+        let checkers = state.checkers;
+        if checkers.contains_multiple() {
+            return false;
+        }
+        if checkers.is_not_empty() {
+            let checker_sq = checkers.lsb_square();
+            if !(checkers | between(king_sq, checker_sq)).contains(action.to()) {
+                return false;
+            }
+        }
+
+        // a pinned piece is fine as long as it stays on the line through the king and its pinner
+        action.is_drop()
+            || !pinned.contains(action.from())
+            || line(king_sq, action.from()).contains(action.to())
+    }
+
+    // calls f with each legal move until it returns false
+    fn for_each_legal_action(&mut self, mut f: impl FnMut(Action) -> bool) {
+        let in_check = self.in_check();
+        let actions = if in_check {
+            self.get_evasions()
+        } else {
+            self.get_actions()
+        };
+
+        let state = self.current_state();
+        let pinned = state.pinned(self.stm);
+        let their_king = state.sided_piece(Piece::KING.raw(), self.stm.flip());
+
+        for action in &actions {
+            // uchifuzume needs the replies searched, so pawn drops that check still get made
+            let checking_pawn_drop = action.is_drop()
+                && action.piece().piece() == Piece::PAWN
+                && (setwise_pawns(Bitboard::from_square(action.to()), self.stm.as_u8())
+                    & their_king)
+                    .is_not_empty();
+
+            let is_legal = if !checking_pawn_drop {
+                self.is_legal_with_pins(*action, pinned)
+            } else if self.perform_action(*action) {
+                self.unmake_move();
+                true
+            } else {
+                false
+            };
+            if is_legal && !f(*action) {
+                return;
+            }
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.current_state().hash
+    }
+
+    // a key for opening books, built from scratch off the placement, hands and side to move so it
+    // stays the same however the position was reached, even if the search hash picks up more later
+    pub fn position_key(&self) -> u64 {
+        let state = self.current_state();
+        let mut key = if self.stm == Side::Gote {
+            ZOBRIST.stm()
+        } else {
+            0
+        };
+        for sq in state.occupied() {
+            key ^= ZOBRIST.piece(state.piece_on_square(sq), sq);
+        }
+        for side in Side::ALL {
+            for (piece, count) in state.hands[side.as_usize()] {
+                for index in 0..count {
+                    key ^= ZOBRIST.hand(side.as_u8(), piece, index);
+                }
+            }
+        }
+        key
+    }
+
+    // indices of earlier states that are the same position as the current one, most recent first
+    fn repetitions(&self) -> impl Iterator<Item = usize> + '_ {
+        let current = self.current_state();
+        let last = self.states.len() - 1;
+        (0..last).rev().skip(1).step_by(2).filter(move |&i| {
+            self.states[i].hash == current.hash && self.states[i].hands == current.hands
+        })
+    }
+
+    // how many times the current position occurred before
+    pub fn repetition_count(&self) -> u32 {
+        self.repetitions().count() as u32
+    }
+
+    // sennichite is the fourth occurrence of the same position
+    pub fn is_sennichite(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    // the side that gave check with every one of its moves since the first occurrence of the current position
+    pub fn perpetual_checker(&self) -> Option<Side> {
+        let first = self.repetitions().last()?;
+        let last = self.states.len() - 1;
+        // the checked side in states[i] is the side to move there
+        let mut stm_checked_throughout = true;
+        let mut opp_checked_throughout = true;
+        for i in first + 1..=last {
+            let checked = self.states[i].checkers.is_not_empty();
+            if (last - i).is_multiple_of(2) {
+                stm_checked_throughout &= checked;
+            } else {
+                opp_checked_throughout &= checked;
+            }
+        }
+        if stm_checked_throughout {
+            Some(self.stm.flip())
+        } else if opp_checked_throughout {
+            Some(self.stm)
+        } else {
+            None
+        }
+    }
+
+    // perpetual check is a loss for the side giving it rather than a draw
+    pub fn is_perpetual_check(&self) -> bool {
+        self.perpetual_checker().is_some()
+    }
+
+    // sennichite that isn't perpetual check, which is a loss for the checker and not a draw
+    pub fn is_draw(&self) -> bool {
+        self.is_sennichite() && !self.is_perpetual_check()
+    }
+
+    // None while the game is still going
+    pub fn game_result(&mut self) -> Option<GameResult> {
+        if !self.has_legal_move() {
+            return Some(if self.in_check() {
+                GameResult::Checkmate
+            } else {
+                GameResult::Stalemate
+            });
+        }
+        if self.is_sennichite() {
+            return Some(if self.is_perpetual_check() {
+                GameResult::PerpetualCheckLoss
+            } else {
+                GameResult::Repetition
+            });
+        }
+        if self.is_impasse_win(self.stm) {
+            return Some(GameResult::Impasse);
+        }
+        if self.ply > self.max_ply {
+            return Some(GameResult::MaxPly);
+        }
+        None
+    }
+
+    pub fn piece_on_square(&self, sq: Square) -> Piece {
+        self.current_state().piece_on_square(sq)
+    }
+
+    pub fn sided_piece(&self, piece: u8, side: Side) -> Bitboard {
+        self.current_state().sided_piece(piece, side)
+    }
+
+    pub fn material(&self, side: Side) -> i32 {
+        self.current_state().material(side)
+    }
+
+    pub fn hand(&self, side: Side) -> Hand {
+        self.current_state().hands[side.as_usize()]
+    }
+
+    #[must_use]
+    pub const fn stm(&self) -> Side {
+        self.stm
+    }
+
+    // the side to move as a raw 0 or 1, for code that indexes with it directly
+    pub fn side_to_move(&self) -> u8 {
+        self.stm.as_u8()
+    }
+
+    // the sfen move number as stored, see move_number for it as a u32
+    #[must_use]
+    pub const fn ply(&self) -> i16 {
+        self.ply
+    }
+
+    // moves since the last capture, pawn move or pawn drop, shogi has no rule for it but it's a
+    // cheap sign that a self-play game has stalled, starts from 0 on loading a position
+    #[must_use]
+    pub fn halfmove_clock(&self) -> u16 {
+        self.current_state().halfmove_clock
+    }
+
+    // the latest position in the history, for reading its bitboards directly
+    #[must_use]
+    pub fn current_position(&self) -> &Position {
+        self.current_state()
+    }
+
+    // 27-point rule scoring, 5 for each rook or bishop and 1 for anything else in the zone or in hand,
+    // only counts once both kings have entered
+    pub fn impasse_points(&self, side: Side) -> u32 {
+        let state = self.current_state();
+        for king_side in Side::ALL {
+            let king = state.sided_piece(Piece::KING.raw(), king_side);
+            if (king & Bitboard::promotion_zone(king_side)).is_empty() {
+                return 0;
+            }
+        }
+        self.zone_points(side)
+    }
+
+    // the points half of the 27-point rule without caring where the kings are
+    fn zone_points(&self, side: Side) -> u32 {
+        let state = self.current_state();
+        let ours = state.sides[side.as_usize()]
+            & Bitboard::promotion_zone(side)
+            & !state.pieces[Piece::KING.as_usize()];
+        let big_pieces = state.pieces[Piece::BISHOP.as_usize()]
+            | state.pieces[Piece::ROOK.as_usize()]
+            | state.pieces[Piece::PROMO_BISHOP.as_usize()]
+            | state.pieces[Piece::PROMO_ROOK.as_usize()];
+        let board_points = 5 * (ours & big_pieces).popcount() + (ours & !big_pieces).popcount();
+
+        let hand = state.hands[side.as_usize()];
+        let big_in_hand = u32::from(hand.count(Piece::BISHOP)) + u32::from(hand.count(Piece::ROOK));
+        let hand_points = 5 * big_in_hand + (hand.total() - big_in_hand);
+
+        board_points + hand_points
+    }
+
+    // sente needs 28 points and gote 27
+    pub fn is_impasse_win(&self, side: Side) -> bool {
+        self.impasse_points(side) >= if side == Side::Sente { 28 } else { 27 }
+    }
+
+    // a jishogi declaration, the king in the zone with 10 other pieces there, enough points and
+    // not in check, the other king doesn't have to have entered
+    pub fn can_declare_win(&self, side: Side) -> bool {
+        let state = self.current_state();
+        let Some(king_sq) = state.try_king_square(side) else {
+            return false;
+        };
+        let zone = Bitboard::promotion_zone(side);
+        if !zone.contains(king_sq) {
+            return false;
+        }
+        // the king is one of the pieces in the zone
+        let others_in_zone = (state.sides[side.as_usize()] & zone).popcount() - 1;
+        others_in_zone >= 10
+            && self.zone_points(side) >= if side == Side::Sente { 28 } else { 27 }
+            && self.attackers_to(king_sq, side.flip()).is_empty()
+    }
+
+    // whether an action would check the enemy king, directly or by uncovering a slider,
+    // looks at the pieces after the move without making it
+    pub fn gives_check(&self, action: Action) -> bool {
+        let state = self.current_state();
+        let Some(king_sq) = state.try_king_square(self.stm.flip()) else {
+            return false;
+        };
+
+        let mut after = *state;
+        let to = action.to();
+        if action.is_drop() {
+            after.add_piece(to, action.piece());
+        } else {
+            let from = action.from();
+            let piece = state.piece_on_square(from);
+            let victim = self.captured_piece(action);
+            if victim != Piece::NONE {
+                after.remove_piece(to, victim);
+            }
+            after.remove_piece(from, piece);
+            after.add_piece(
+                to,
+                if action.is_promo() {
+                    piece.promote()
+                } else {
+                    piece
+                },
+            );
+        }
+        after
+            .attackers_to(king_sq, self.stm, after.occupied())
+            .is_not_empty()
+    }
+
+    // everything the piece on sq attacks with the current occupancy, empty squares attack nothing
+    pub fn attacks_from(&self, sq: Square) -> Bitboard {
+        let state = self.current_state();
+        let piece = state.piece_on_square(sq);
+        if piece == Piece::NONE {
+            return Bitboard::EMPTY;
+        }
+        piece_attacks(piece, sq, state.occupied())
+    }
+
+    // a side's pieces attacking a square with the current occupancy
+    pub fn attackers_to(&self, sq: Square, side: Side) -> Bitboard {
+        let state = self.current_state();
+        state.attackers_to(sq, side, state.occupied())
+    }
+
+    // enemy pieces attacking a square
+    pub fn get_attackers(&self, sq: Square) -> Bitboard {
+        let state = self.current_state();
+        state.attackers_to(sq, self.stm.flip(), state.occupied())
+    }
+
+    // uses the cached checkers, which are refreshed at the end of every move
+    pub fn in_check(&self) -> bool {
+        !self.current_state().checkers.is_empty()
+    }
+
+    // computes from scratch whether the side to move's king is attacked
+    // a side without a king is never in check
+    pub fn is_in_check(&self) -> bool {
+        self.current_state()
+            .try_king_square(self.stm)
+            .is_some_and(|king_sq| self.get_attackers(king_sq).is_not_empty())
+    }
+
+    pub fn king_sq(&self) -> Square {
+        self.current_state().king_square(self.stm)
+    }
+
+    pub fn update_checkers(&mut self) {
+        let king_atkers = match self.current_state().try_king_square(self.stm) {
+            Some(king_sq) => self.get_attackers(king_sq),
+            None => Bitboard::EMPTY,
+        };
+        let state = self.current_state_mut();
+        state.checkers = king_atkers;
+    }
+
+    // applies an action without checking legality
+    pub fn make_move(&mut self, action: Action) {
+        self.apply_action(action);
+        self.end_turn();
+        debug_assert_eq!(self.current_state().debug_validate(), Ok(()));
+    }
+
+    // applies an action, returns false (and leaves the board untouched) if it leaves our king in check
+    pub fn perform_action(&mut self, action: Action) -> bool {
+        self.apply_action(action);
+        // legality check, stm hasn't been flipped yet so this is the mover's king
+        if self.is_in_check() {
+            self.states.pop();
+            return false;
+        }
+        self.end_turn();
+
+        // uchifuzume, dropping a pawn to give checkmate is illegal, only drops though, a pawn moving
+        // or promoting into mate is fine and so is a dropped pawn check that can be answered
+        if action.is_drop()
+            && action.piece().piece() == Piece::PAWN
+            && self.in_check()
+            && !self.has_legal_move()
+        {
+            self.unmake_move();
+            return false;
+        }
+        true
+    }
+
+    pub fn unmake_move(&mut self) {
+        self.states.pop();
+        self.ply -= 1;
+        self.stm = self.stm.flip();
+        debug_assert_eq!(self.current_state().debug_validate(), Ok(()));
+    }
+
+    // how many positions the history holds, the loaded one included
+    pub fn history_len(&self) -> usize {
+        self.states.len()
+    }
+
+    // takes the history back to an earlier position, 0 being the one it started from,
+    // like calling unmake_move until only ply + 1 positions are left
+    pub fn goto_ply(&mut self, ply: usize) {
+        assert!(
+            ply < self.states.len(),
+            "ply {ply} is past the end of the history"
+        );
+        let undone = self.states.len() - 1 - ply;
+        self.states.truncate(ply + 1);
+        if undone % 2 == 1 {
+            self.stm = self.stm.flip();
+        }
+        self.ply -= undone as i16;
+    }
+
+    // passes the turn without moving anything, for null move pruning
+    // never call this while in check, the side to move would be left able to capture the king
+    pub fn make_null_move(&mut self) {
+        debug_assert!(!self.in_check(), "null move while in check");
+        self.states.push(*self.current_state());
+        self.end_turn();
+    }
+
+    pub fn unmake_null_move(&mut self) {
+        self.unmake_move();
+    }
+
+    fn apply_action(&mut self, action: Action) {
+        let victim = self.captured_piece(action);
+        self.states.push(*self.current_state());
+        // just like in anura, not using self.current_state_mut() because of borrowing shenanigans
+        let state = self.states.last_mut().expect("no position");
+        let moved = if action.is_drop() {
+            action.piece()
+        } else {
+            state.piece_on_square(action.from())
+        };
+        state.halfmove_clock = if victim != Piece::NONE || moved.piece() == Piece::PAWN {
+            0
+        } else {
+            state.halfmove_clock.saturating_add(1)
+        };
+        if action.is_drop() {
+            let to = action.to();
+            let piece = action.piece();
+            state.add_piece(to, piece);
+            state.remove_from_hand(self.stm, piece.unpromote());
+        } else {
+            let from = action.from();
+            let to = action.to();
             let piece = state.piece_on_square(from);
-            let victim = state.piece_on_square(to);
-            state.remove_piece(from, piece);
             if victim != Piece::NONE {
-                state.remove_piece(to, victim);
-                state.hands[self.stm as usize].inc(victim.unpromote())
+                // captured pieces go to our hand unpromoted
+                state.add_to_hand(self.stm, victim);
             }
             if action.is_promo() {
+                if victim != Piece::NONE {
+                    state.remove_piece(to, victim);
+                }
+                state.remove_piece(from, piece);
                 state.add_piece(to, piece.promote());
             } else {
-                state.add_piece(to, piece);
+                state.move_piece(from, piece, to, victim);
             }
         }
+    }
 
+    fn end_turn(&mut self) {
+        self.current_state_mut().hash ^= ZOBRIST.stm();
+        self.stm = self.stm.flip();
         self.ply += 1;
-        // legality check
-        if !self.get_attackers(self.king_sq()).is_empty() {
-            self.undo_action();
-            self.stm = 1 - self.stm;
-            false
-        } else {
-            self.stm = 1 - self.stm;
-            true
+        self.update_checkers();
+    }
+}
+
+// builds a position piece by piece and only hands over a board once it passes the same checks
+// as try_load_fen, along with pieces that could never move
+#[derive(Debug, Clone)]
+pub struct BoardBuilder {
+    mailbox: [Piece; NUM_SQUARES as usize],
+    hands: [[u32; 7]; 2],
+    stm: Side,
+    // the first piece handed to hand that can't go in one, build reports it
+    hand_error: Option<FenError>,
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self {
+            mailbox: [Piece::NONE; NUM_SQUARES as usize],
+            hands: [[0; 7]; 2],
+            stm: Side::Sente,
+            hand_error: None,
         }
     }
+}
 
-    pub fn undo_action(&mut self) {
-        self.states.pop();
-        self.ply -= 1;
-        self.stm = 1 - self.stm;
+impl BoardBuilder {
+    // replaces whatever was on the square, Piece::NONE empties it
+    #[must_use]
+    pub fn place(mut self, sq: Square, piece: Piece) -> Self {
+        self.mailbox[sq.as_usize()] = piece;
+        self
+    }
+
+    #[must_use]
+    pub fn hand(mut self, side: Side, piece: Piece, count: u32) -> Self {
+        match hand_slot(side, piece) {
+            Ok(piece) => self.hands[side.as_usize()][piece.as_usize()] = count,
+            Err(error) => {
+                self.hand_error.get_or_insert(error);
+            }
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn side_to_move(mut self, side: Side) -> Self {
+        self.stm = side;
+        self
+    }
+
+    pub fn build(self) -> Result<Board, FenError> {
+        if let Some(error) = self.hand_error {
+            return Err(error);
+        }
+        let mut state = Position::default();
+        for (i, piece) in self.mailbox.into_iter().enumerate() {
+            if piece != Piece::NONE {
+                state.add_piece(Square(i as u8), piece);
+            }
+        }
+        for side in Side::ALL {
+            for (piece, &count) in self.hands[side.as_usize()].iter().enumerate() {
+                let piece = Piece(piece as u8);
+                if count > Hand::max_count(piece) {
+                    let c = piece.as_stm(side.as_u8()).to_string().remove(0);
+                    return Err(FenError::HandOverflow(c));
+                }
+                state.set_hand(side, piece, count);
+            }
+        }
+
+        check_kings_and_nifu(&state, self.stm, false)?;
+        for side in Side::ALL {
+            let stuck = ((state.sided_piece(Piece::PAWN.raw(), side)
+                | state.sided_piece(Piece::LANCE.raw(), side))
+                & Bitboard::last_rank(side))
+                | (state.sided_piece(Piece::KNIGHT.raw(), side) & Bitboard::last_two_ranks(side));
+            if let Some(sq) = stuck.first_square() {
+                return Err(FenError::StuckPiece(sq));
+            }
+        }
+
+        if self.stm == Side::Gote {
+            state.hash ^= ZOBRIST.stm();
+        }
+        let mut board = Board::default();
+        board.states.clear();
+        board.states.push(state);
+        board.stm = self.stm;
+        board.ply = 1;
+        board.update_checkers();
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_sfen(sfen: &str) -> Board {
+        let mut board = Board::default();
+        board.load_fen(sfen);
+        board
+    }
+
+    fn mv(from: &str, to: &str) -> Action {
+        Action::new_move(
+            Square::from_usi(from).expect("bad square"),
+            Square::from_usi(to).expect("bad square"),
+            false,
+        )
+    }
+
+    #[test]
+    fn pawn_takes_rook_sorts_first() {
+        let board = from_sfen("4k4/9/p8/9/4r3s/4P4/4B4/9/4K3R b - 1");
+        let mut captures = board.get_captures();
+        board.sort_captures(&mut captures);
+        assert_eq!(captures[0], mv("5f", "5e"));
+        assert_eq!(captures[1], mv("1i", "1e"));
+    }
+
+    #[test]
+    fn mvv_lva_puts_the_victim_before_the_attacker() {
+        // a gold is worth more than a tokin whatever takes it
+        let board = from_sfen("4k4/9/9/9/8g/9/4+p4/4P4/4K3R b - 1");
+        assert!(board.mvv_lva(mv("1i", "1e")) > board.mvv_lva(mv("5h", "5g")));
+        // the king taking a gold still beats a rook taking a pawn
+        let board = from_sfen("4k4/9/9/9/8p/9/9/4g4/4K3R b - 1");
+        assert!(board.mvv_lva(mv("5i", "5h")) > board.mvv_lva(mv("1i", "1e")));
+    }
+
+    #[test]
+    fn capturing_a_dragon_puts_a_rook_in_the_capturers_hand() {
+        let mut board = from_sfen("4k4/9/9/9/4+r4/4S4/9/9/4K4 b - 1");
+        board.make_move(mv("5f", "5e"));
+        assert_eq!(board.hand(Side::Sente).count(Piece::ROOK), 1);
+        assert_eq!(board.hand(Side::Gote).total(), 0);
+
+        let mut board = from_sfen("4k4/9/9/4s4/4+B4/9/9/9/4K4 w - 1");
+        board.make_move(mv("5d", "5e"));
+        assert_eq!(board.hand(Side::Gote).count(Piece::BISHOP), 1);
+        assert_eq!(board.hand(Side::Sente).total(), 0);
+    }
+
+    #[test]
+    fn rook_gives_check_down_an_open_file() {
+        let board = from_sfen("4k4/9/9/9/4r4/9/9/9/4K4 b - 1");
+        assert!(board.is_in_check());
+        let blocked = from_sfen("4k4/9/9/9/4r4/4P4/9/9/4K4 b - 1");
+        assert!(!blocked.is_in_check());
+        // the check is only against the side to move
+        let gote = from_sfen("4k4/9/9/9/4r4/9/9/9/4K4 w - 1");
+        assert!(!gote.is_in_check());
+    }
+
+    #[test]
+    fn legal_actions_drop_whatever_leaves_the_king_in_check() {
+        let mut board = from_sfen("4k4/9/9/9/4r4/9/9/3G1G3/4K4 b - 1");
+        let legal = board.get_legal_actions();
+        assert!(board.get_actions().len() > legal.len());
+        // stepping aside or putting a gold in the way, 5h is still on the rook's file
+        let mut moves: Vec<String> = legal.iter().map(ToString::to_string).collect();
+        moves.sort();
+        assert_eq!(moves, ["4h5g", "4h5h", "5i4i", "5i6i", "6h5g", "6h5h"]);
+    }
+
+    #[test]
+    fn pawn_drop_mate_is_illegal_but_a_lance_drop_mate_is_not() {
+        let mut board = from_sfen("7nk/9/7G1/9/9/9/9/9/4K4 b LP 1");
+        let legal = board.get_legal_actions();
+        let pawn_drop = board.action_from_usi("P*1b");
+        assert_eq!(pawn_drop, None);
+        assert!(!legal.iter().any(|action| action.to_string() == "P*1b"));
+
+        let lance_drop = board.action_from_usi("L*1b").expect("lance drop refused");
+        assert!(legal.contains(&lance_drop));
+        board.make_move(lance_drop);
+        assert!(board.in_check() && !board.has_legal_move());
+    }
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for usi in moves {
+            let action = board.action_from_usi(usi).expect("illegal move");
+            board.make_move(action);
+        }
+    }
+
+    #[test]
+    fn unmaking_restores_the_hash() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        let start = board.hash();
+        // a capture, a promotion and a drop all get undone
+        play(&mut board, &["7g7f", "3c3d", "8h2b+", "3a2b", "B*4e"]);
+        assert_ne!(board.hash(), start);
+        for _ in 0..5 {
+            board.unmake_move();
+        }
+        assert_eq!(board.hash(), start);
+    }
+
+    #[test]
+    fn transpositions_hash_the_same() {
+        let mut first = from_sfen(STARTPOS_SFEN);
+        play(&mut first, &["7g7f", "3c3d", "2g2f", "8c8d"]);
+        let mut second = from_sfen(STARTPOS_SFEN);
+        play(&mut second, &["2g2f", "8c8d", "7g7f", "3c3d"]);
+        assert_eq!(first.hash(), second.hash());
+        // and the same as loading the position outright
+        assert_eq!(first.hash(), from_sfen(&first.to_sfen()).hash());
+        // the side to move is part of the key
+        assert_ne!(
+            from_sfen("4k4/9/9/9/9/9/9/9/4K4 b - 1").hash(),
+            from_sfen("4k4/9/9/9/9/9/9/9/4K4 w - 1").hash()
+        );
+    }
+
+    #[test]
+    fn shuffling_kings_reach_sennichite() {
+        let mut board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b - 1");
+        let cycle = ["5i4i", "5a4a", "4i5i", "4a5a"];
+        for occurrence in 1..4 {
+            play(&mut board, &cycle);
+            assert_eq!(board.repetition_count(), occurrence);
+            assert_eq!(board.is_sennichite(), occurrence == 3);
+        }
+        assert!(!board.is_perpetual_check());
+        assert!(board.is_draw());
+    }
+
+    #[test]
+    fn checking_every_move_is_perpetual_check() {
+        // gote steps between 1a and 2a and the rook follows it with check
+        let mut board = from_sfen("8k/9/9/9/9/9/9/9/4K3R w - 1");
+        for _ in 0..3 {
+            play(&mut board, &["1a2a", "1i2i", "2a1a", "2i1i"]);
+        }
+        assert!(board.is_sennichite());
+        assert!(board.is_perpetual_check());
+        assert_eq!(board.perpetual_checker(), Some(Side::Sente));
+        assert!(!board.is_draw());
+    }
+
+    #[test]
+    fn sfen_round_trips() {
+        for sfen in [
+            STARTPOS_SFEN,
+            "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+            "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+            "8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124",
+            "4k4/9/9/9/9/9/9/9/4K4 b 2R2B4G4S4N4L18P 57",
+        ] {
+            assert_eq!(from_sfen(sfen).to_sfen(), sfen);
+        }
+    }
+
+    #[test]
+    fn usi_moves_round_trip() {
+        let board = from_sfen("4k4/9/9/4P4/9/9/9/9/4K4 b RBGSNLP 1");
+        let moves = [
+            "5i4h", "5d5c", "5d5c+", "R*2e", "B*2e", "G*2e", "S*2e", "N*2e", "L*2e", "P*2e",
+        ];
+        for usi in moves {
+            let action = board.action_from_usi(usi).expect("legal move refused");
+            assert_eq!(action.to_usi(), usi);
+        }
+        assert!(board
+            .action_from_usi("5d5c")
+            .is_some_and(|action| !action.is_promo()));
+        assert!(board.action_from_usi("N*2a").is_none());
+        assert!(board.action_from_usi("5d5").is_none());
+
+        // gote drops are written uppercase too
+        let board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 w p 1");
+        let drop = board.action_from_usi("P*5e").expect("legal drop refused");
+        assert_eq!(drop.to_usi(), "P*5e");
+    }
+
+    fn squares(usi: &[&str]) -> Bitboard {
+        usi.iter()
+            .map(|usi| Square::from_usi(usi).expect("bad square"))
+            .collect()
+    }
+
+    #[test]
+    fn attackers_hide_behind_the_first_slider() {
+        // a lance in front of a rook on the file and a horse in front of a bishop on the diagonal
+        let board = from_sfen("k3r3b/4l4/6+b2/9/9/4G4/9/9/4K4 b - 1");
+        let target = Square::from_usi("5e").expect("bad square");
+        let state = board.current_position();
+        assert_eq!(
+            state.attackers_to(target, Side::Gote, state.occupied()),
+            squares(&["5b", "3c"])
+        );
+        // taking the front pieces away shows what was behind them
+        let xray = state.occupied() ^ squares(&["5b", "3c"]);
+        assert_eq!(
+            state.attackers_to(target, Side::Gote, xray),
+            squares(&["5b", "3c", "5a", "1a"])
+        );
+        assert_eq!(board.attackers_to(target, Side::Sente), squares(&["5f"]));
+    }
+
+    fn targets_from(board: &Board, from: &str) -> Bitboard {
+        let from = Square::from_usi(from).expect("bad square");
+        board
+            .legal_moves_from(from)
+            .iter()
+            .map(|action| action.to())
+            .collect()
+    }
+
+    #[test]
+    fn pinned_pieces_only_move_along_the_pin() {
+        // a lance pinning a silver on the king's file
+        let board = from_sfen("k3l4/9/9/9/9/9/4S4/9/4K4 b - 1");
+        assert_eq!(
+            board.current_position().pinned(Side::Sente),
+            squares(&["5g"])
+        );
+        assert_eq!(targets_from(&board, "5g"), squares(&["5f"]));
+
+        // a bishop pinning a gold on the diagonal, its only step along the ray is towards the bishop
+        let board = from_sfen("k8/9/9/9/8b/9/6G2/9/4K4 b - 1");
+        assert_eq!(
+            board.current_position().pinned(Side::Sente),
+            squares(&["3g"])
+        );
+        assert_eq!(targets_from(&board, "3g"), squares(&["2f"]));
+
+        // with a second piece in the way nothing is pinned
+        let board = from_sfen("k3l4/9/9/9/4P4/9/4S4/9/4K4 b - 1");
+        assert!(board.current_position().pinned(Side::Sente).is_empty());
+    }
+
+    #[test]
+    fn startpos_has_forty_pieces_and_empty_hands() {
+        let mut board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b 2P 1");
+        board.set_startpos();
+        assert_eq!(board.current_position().occupied().popcount(), 40);
+        for side in Side::ALL {
+            assert_eq!(board.hand(side).total(), 0);
+        }
+        assert_eq!(board.to_sfen(), STARTPOS_SFEN);
+    }
+
+    #[test]
+    fn loading_twice_starts_a_fresh_history() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        play(&mut board, &["7g7f", "3c3d"]);
+        let second =
+            "8l/1l+R2P3/p2pBG1pp/kps1p4/Nn1P2G2/P1P1P2PP/1PS6/1KSG3+r1/LN2+p3L w Sbgn3p 124";
+        board.load_fen(second);
+        assert_eq!(board.history_len(), 1);
+        assert_eq!(board.to_sfen(), second);
+        assert_eq!(board.stm(), Side::Gote);
+        assert_eq!(board.repetition_count(), 0);
+    }
+
+    #[test]
+    fn a_tokin_or_an_enemy_pawn_doesnt_block_a_pawn_drop() {
+        // a tokin on the 5 file, our pawn on the 1 file and gote's pawn on the 7 file
+        let board = from_sfen("4k4/9/2p6/9/9/4+P4/9/8P/4K4 b P 1");
+        assert!(board.action_from_usi("P*5e").is_some());
+        assert!(board.action_from_usi("P*7e").is_some());
+        assert!(board.action_from_usi("P*1e").is_none());
+        assert!(!board
+            .get_drops()
+            .iter()
+            .any(|action| action.to_string() == "P*1e"));
+    }
+
+    fn moves_from(board: &Board, from: &str) -> Vec<String> {
+        let from = Square::from_usi(from).expect("bad square");
+        let mut moves: Vec<String> = board
+            .get_actions()
+            .iter()
+            .filter(|action| !action.is_drop() && action.from() == from)
+            .map(ToString::to_string)
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn dead_end_moves_have_to_promote() {
+        let board = from_sfen("k8/9/4P4/9/9/9/9/9/4K4 b - 1");
+        assert_eq!(moves_from(&board, "5c"), ["5c5b", "5c5b+"]);
+        let board = from_sfen("k8/4P4/9/9/9/9/9/9/4K4 b - 1");
+        assert_eq!(moves_from(&board, "5b"), ["5b5a+"]);
+
+        let board = from_sfen("k8/9/9/9/4N4/9/9/9/4K4 b - 1");
+        assert_eq!(moves_from(&board, "5e"), ["5e4c", "5e4c+", "5e6c", "5e6c+"]);
+        let board = from_sfen("k8/9/9/4N4/9/9/9/9/4K4 b - 1");
+        assert_eq!(moves_from(&board, "5d"), ["5d4b+", "5d6b+"]);
+
+        // and the same from gote's side of the board
+        let board = from_sfen("4k4/9/9/9/9/9/9/4p4/K8 w - 1");
+        assert_eq!(moves_from(&board, "5h"), ["5h5i+"]);
+        let board = from_sfen("4k4/9/9/9/9/4n4/9/9/K8 w - 1");
+        assert_eq!(moves_from(&board, "5f"), ["5f4h+", "5f6h+"]);
+    }
+
+    fn lines_through(usi: &str) -> Bitboard {
+        let sq = Square::from_usi(usi).expect("bad square");
+        (Bitboard::from_rank(sq.rank()) | Bitboard::from_file(sq.file()))
+            ^ Bitboard::from_square(sq)
+    }
+
+    #[test]
+    fn dragons_and_horses_add_the_missing_king_steps() {
+        // the kings sit off every line the piece could use
+        let board = from_sfen("1k7/9/9/9/4+R4/9/9/9/7K1 b - 1");
+        assert_eq!(
+            targets_from(&board, "5e"),
+            lines_through("5e") | squares(&["4d", "6d", "4f", "6f"])
+        );
+        let board = from_sfen("1k7/9/9/9/4+B4/9/9/9/7K1 b - 1");
+        assert_eq!(
+            targets_from(&board, "5e"),
+            squares(&["1a", "2b", "3c", "4d", "6f", "7g", "8h", "9i"])
+                | squares(&["9a", "8b", "7c", "6d", "4f", "3g", "2h", "1i"])
+                | squares(&["5d", "5f", "4e", "6e"])
+        );
+
+        let board = from_sfen("8+R/9/4k4/9/9/9/9/9/4K4 b - 1");
+        assert_eq!(
+            targets_from(&board, "1a"),
+            lines_through("1a") | squares(&["2b"])
+        );
+        let board = from_sfen("8+B/9/4k4/9/9/9/9/9/4K4 b - 1");
+        assert_eq!(
+            targets_from(&board, "1a"),
+            squares(&["2b", "3c", "4d", "5e", "6f", "7g", "8h", "9i", "2a", "1b"])
+        );
+    }
+
+    #[test]
+    fn see_scores_the_whole_exchange() {
+        let pawn = PIECE_VALUES[Piece::PAWN.as_usize()];
+        let lance = PIECE_VALUES[Piece::LANCE.as_usize()];
+        let gold = PIECE_VALUES[Piece::GOLD.as_usize()];
+        let rook = PIECE_VALUES[Piece::ROOK.as_usize()];
+
+        // a free pawn
+        let board = from_sfen("4k4/9/9/9/8p/9/9/9/4K3R b - 1");
+        assert_eq!(board.see(mv("1i", "1e")), pawn);
+        // silver for silver
+        let board = from_sfen("4k4/9/9/4p4/4s4/4S4/9/9/4K4 b - 1");
+        assert_eq!(board.see(mv("5f", "5e")), 0);
+        // a pawn guarded twice, the lance backing the rook up only trades itself off too
+        let board = from_sfen("4k4/9/9/7gg/8p/9/9/8R/4K3L b - 1");
+        assert_eq!(board.see(mv("1h", "1e")), pawn - rook + gold - lance);
+        // the second rook only joins in once the first one has moved off the file
+        let board = from_sfen("4k4/9/9/8g/8p/9/9/8R/4K3R b - 1");
+        assert_eq!(board.see(mv("1h", "1e")), pawn - rook + gold);
+    }
+
+    #[test]
+    fn drops_sort_after_every_capture() {
+        let board = from_sfen("4k4/9/9/9/8p/9/9/9/4K3R b P 1");
+        let mut actions = board.get_actions();
+        board.sort_captures(&mut actions);
+        assert_eq!(actions[0], mv("1i", "1e"));
+        assert!(actions.last().is_some_and(Action::is_drop));
+    }
+
+    #[test]
+    fn entering_kings_score_impasse_points() {
+        // sente has a rook and bishop in the zone and the rest in hand for exactly 28
+        let board = from_sfen("7BR/4K4/9/9/9/9/9/4k4/9 b RB4G4S4n4l18p 1");
+        assert_eq!(board.impasse_points(Side::Sente), 28);
+        assert_eq!(board.impasse_points(Side::Gote), 26);
+        assert!(board.is_impasse_win(Side::Sente));
+        assert!(!board.is_impasse_win(Side::Gote));
+
+        // nothing counts until both kings are in
+        let board = from_sfen("7BR/4K4/9/9/4k4/9/9/9/9 b RB4G4S4n4l18p 1");
+        assert_eq!(board.impasse_points(Side::Sente), 0);
+        assert!(!board.is_impasse_win(Side::Sente));
+    }
+
+    fn sorted_usi(actions: &Actionlist) -> Vec<String> {
+        let mut moves: Vec<String> = actions.iter().map(ToString::to_string).collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn evading_a_slider_blocks_captures_or_steps_away() {
+        let mut board = from_sfen("k1B6/9/4r4/9/9/9/9/9/4K4 b G 1");
+        assert_eq!(
+            sorted_usi(&board.get_legal_actions()),
+            [
+                "5i4h", "5i4i", "5i6h", "5i6i", "7a5c", "7a5c+", "G*5d", "G*5e", "G*5f", "G*5g",
+                "G*5h"
+            ]
+        );
+    }
+
+    #[test]
+    fn a_knight_check_cant_be_blocked() {
+        let mut board = from_sfen("k8/9/9/9/9/9/5n3/5G3/4K4 b G 1");
+        let evasions = board.get_evasions();
+        assert!(!evasions.iter().any(|action| action.is_drop()));
+        assert!(evasions.contains(&mv("4h", "4g")));
+        assert_eq!(
+            sorted_usi(&board.get_legal_actions()),
+            ["4h4g", "5i4i", "5i5h", "5i6h", "5i6i"]
+        );
+    }
+
+    #[test]
+    fn double_check_only_moves_the_king() {
+        let mut board = from_sfen("k3r4/9/9/9/9/9/5n3/5G3/4K4 b G 1");
+        let king = Square::from_usi("5i").expect("bad square");
+        assert!(board
+            .get_evasions()
+            .iter()
+            .all(|action| !action.is_drop() && action.from() == king));
+        assert_eq!(
+            sorted_usi(&board.get_legal_actions()),
+            ["5i4i", "5i6h", "5i6i"]
+        );
+    }
+
+    #[test]
+    fn game_results_cover_every_ending() {
+        assert_eq!(from_sfen(STARTPOS_SFEN).game_result(), None);
+        assert_eq!(
+            from_sfen("7nk/8L/7G1/9/9/9/9/9/4K4 w - 1").game_result(),
+            Some(GameResult::Checkmate)
+        );
+        // the knight and silver cover every square around the king without checking it
+        assert_eq!(
+            from_sfen("8k/9/6N1S/9/9/9/9/9/4K4 w - 1").game_result(),
+            Some(GameResult::Stalemate)
+        );
+
+        let mut board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b - 1");
+        for _ in 0..3 {
+            play(&mut board, &["5i4i", "5a4a", "4i5i", "4a5a"]);
+        }
+        assert_eq!(board.game_result(), Some(GameResult::Repetition));
+
+        let mut board = from_sfen("8k/9/9/9/9/9/9/9/4K3R w - 1");
+        for _ in 0..3 {
+            play(&mut board, &["1a2a", "1i2i", "2a1a", "2i1i"]);
+        }
+        assert_eq!(board.game_result(), Some(GameResult::PerpetualCheckLoss));
+
+        assert_eq!(
+            from_sfen("7BR/4K4/9/9/9/9/9/4k4/9 b RB4G4S4n4l18p 1").game_result(),
+            Some(GameResult::Impasse)
+        );
+
+        let mut board = from_sfen(STARTPOS_SFEN);
+        board.set_max_ply(2);
+        play(&mut board, &["7g7f", "3c3d"]);
+        assert_eq!(board.game_result(), Some(GameResult::MaxPly));
+    }
+
+    #[test]
+    fn null_moves_pass_the_turn_and_undo_cleanly() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        let (hash, stm, ply) = (board.hash(), board.stm(), board.ply());
+        board.make_null_move();
+        assert_eq!(board.stm(), stm.flip());
+        assert_ne!(board.hash(), hash);
+        assert_eq!(board.current_position().occupied().popcount(), 40);
+        board.unmake_null_move();
+        assert_eq!((board.hash(), board.stm(), board.ply()), (hash, stm, ply));
+        assert_eq!(board.to_sfen(), STARTPOS_SFEN);
+    }
+
+    #[test]
+    fn corrupted_positions_fail_validation() {
+        let board = from_sfen(STARTPOS_SFEN);
+        let position = *board.current_position();
+        assert_eq!(position.debug_validate(), Ok(()));
+        let empty = Square::from_usi("5e").expect("bad square");
+
+        // a rook that's only in its piece bitboard
+        let mut corrupt = position;
+        corrupt.pieces[Piece::ROOK.as_usize()].set(empty);
+        assert!(corrupt.debug_validate().is_err());
+
+        // a square claimed by both sides
+        let mut corrupt = position;
+        let king = position.king_square(Side::Sente);
+        corrupt.sides[Side::Gote.as_usize()].set(king);
+        assert!(corrupt.debug_validate().is_err());
+
+        // a piece in the mailbox that no bitboard knows about
+        let mut corrupt = position;
+        corrupt.mailbox[empty.as_usize()] = Piece::GOLD;
+        assert!(corrupt.debug_validate().is_err());
+    }
+
+    #[test]
+    fn the_move_number_is_optional() {
+        let bare = "4k4/9/9/9/9/9/9/9/4K4 b -";
+        assert_eq!(from_sfen(bare).ply(), 1);
+        assert_eq!(from_sfen(&format!("{bare} 37")).ply(), 37);
+        // anything after the move number is ignored
+        assert_eq!(from_sfen(&format!("{bare} 37 extra")).ply(), 37);
+
+        let mut board = Board::default();
+        assert_eq!(
+            board.try_load_fen(&format!("{bare} x")),
+            Err(FenError::BadPly("x".to_owned()))
+        );
+        assert_eq!(
+            board.try_load_fen(&format!("{bare} -3")),
+            Err(FenError::BadPly("-3".to_owned()))
+        );
+    }
+
+    #[test]
+    fn startpos_kings_are_on_their_home_squares() {
+        let board = from_sfen(STARTPOS_SFEN);
+        let state = board.current_position();
+        assert_eq!(state.king_square(Side::Sente).to_string(), "5i");
+        assert_eq!(state.king_square(Side::Gote).to_string(), "5a");
+        assert_eq!(state.try_king_square(Side::Gote), Square::from_usi("5a"));
+
+        // tsume positions can leave the attacker without one
+        let mut board = Board::default();
+        board.set_tsume_mode(true);
+        board.load_fen("4k4/9/9/9/9/9/9/9/9 b G 1");
+        assert_eq!(board.current_position().try_king_square(Side::Sente), None);
+    }
+
+    #[test]
+    fn legal_move_counts_match_the_generated_lists() {
+        for sfen in [
+            STARTPOS_SFEN,
+            "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+            "4k4/9/9/9/4r4/9/9/3G1G3/4K4 b - 1",
+            "7nk/9/7G1/9/9/9/9/9/4K4 b LP 1",
+            "7nk/8L/7G1/9/9/9/9/9/4K4 w - 1",
+        ] {
+            let mut board = from_sfen(sfen);
+            let count = board.get_legal_actions().len();
+            assert_eq!(board.legal_move_count(), count, "{sfen}");
+            assert_eq!(board.has_legal_move(), count > 0, "{sfen}");
+        }
+    }
+
+    fn drop_count(board: &Board, piece: Piece) -> usize {
+        board
+            .get_drops()
+            .iter()
+            .filter(|action| action.piece().piece() == piece)
+            .count()
+    }
+
+    #[test]
+    fn drop_counts_follow_the_restrictions() {
+        // 79 empty squares, pawns can't go on the 8 in the last rank and knights the 17 in the last two
+        let board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b N2P 1");
+        assert_eq!(drop_count(&board, Piece::PAWN), 71);
+        assert_eq!(drop_count(&board, Piece::KNIGHT), 62);
+        assert_eq!(board.get_drops().len(), 133);
+
+        // a pawn on the board takes the 7 empty squares of its file out as well
+        let board = from_sfen("4k4/9/9/9/9/9/2P6/9/4K4 b N2P 1");
+        assert_eq!(drop_count(&board, Piece::PAWN), 63);
+        assert_eq!(drop_count(&board, Piece::KNIGHT), 61);
+    }
+
+    #[test]
+    fn startpos_has_twenty_pieces_a_side() {
+        let board = from_sfen(STARTPOS_SFEN);
+        let state = board.current_position();
+        for side in Side::ALL {
+            let pieces: Vec<(Square, Piece)> = state.pieces_of(side).collect();
+            assert_eq!(pieces.len(), 20);
+            let mut counts = [0; NUM_PIECE_TYPES as usize];
+            for (sq, piece) in pieces {
+                assert_eq!(piece, state.piece_on_square(sq));
+                assert_eq!(piece.side(), side.as_u8());
+                counts[piece.piece().as_usize()] += 1;
+            }
+            // pawns, lances, knights, silvers, the bishop, the rook, golds and the king
+            assert_eq!(counts[..8], [9, 2, 2, 2, 1, 1, 2, 1]);
+        }
+    }
+
+    #[test]
+    fn a_diagonally_pinned_silver_stays_on_the_diagonal() {
+        let board = from_sfen("k8/9/9/9/8b/9/9/5S3/4K4 b - 1");
+        assert_eq!(targets_from(&board, "4h"), squares(&["3g"]));
+        // and it can take the pinner if it's close enough
+        let board = from_sfen("k8/9/9/9/9/9/6b2/5S3/4K4 b - 1");
+        assert_eq!(targets_from(&board, "4h"), squares(&["3g"]));
+        // without the bishop every step is open but the one onto its own king
+        let board = from_sfen("k8/9/9/9/9/9/9/5S3/4K4 b - 1");
+        assert_eq!(
+            targets_from(&board, "4h"),
+            squares(&["4g", "3g", "5g", "3i"])
+        );
+    }
+
+    #[test]
+    fn make_and_unmake_give_back_an_equal_position() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        let start = *board.current_position();
+        play(&mut board, &["7g7f", "3c3d", "8h2b+"]);
+        assert_ne!(*board.current_position(), start);
+        for _ in 0..3 {
+            board.unmake_move();
+        }
+        assert_eq!(*board.current_position(), start);
+
+        // equality is about the pieces, the hash isn't compared
+        let mut rehashed = start;
+        rehashed.hash ^= 1;
+        assert_eq!(rehashed, start);
+    }
+
+    #[test]
+    fn malformed_boards_are_refused_untouched() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        for (sfen, error) in [
+            ("4k4/10/9/9/9/9/9/9/4K4 b - 1", FenError::FileOverflow),
+            ("4k4/4P5/9/9/9/9/9/9/4K4 b - 1", FenError::FileOverflow),
+            ("4k4/9/9/9/9/9/9/9/4K4/9 b - 1", FenError::TooManyRanks),
+            ("4k4/8/9/9/9/9/9/9/4K4 b - 1", FenError::MissingSquares),
+            ("4k4/9/9/9/9/9/9/4K4 b - 1", FenError::MissingSquares),
+        ] {
+            assert_eq!(board.try_load_fen(sfen), Err(error), "{sfen}");
+            assert_eq!(board.to_sfen(), STARTPOS_SFEN);
+        }
+    }
+
+    #[test]
+    fn checks_are_spotted_before_the_move() {
+        // the gold steps up next to the king
+        let board = from_sfen("4k4/9/4G4/9/9/9/9/9/4K4 b - 1");
+        assert!(board.gives_check(mv("5c", "5b")));
+        assert!(!board.gives_check(mv("5c", "4c")));
+        // drops count too
+        let board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b R 1");
+        let drop = board.action_from_usi("R*5e").expect("legal drop refused");
+        assert!(board.gives_check(drop));
+        // the silver stepping off the file uncovers the rook behind it
+        let board = from_sfen("4k4/9/9/9/4S4/9/9/4R4/K8 b - 1");
+        assert!(board.gives_check(mv("5e", "4d")));
+        // moving along the file keeps it blocked
+        assert!(!board.gives_check(mv("5e", "5d")));
+
+        for action in &board.get_actions() {
+            let mut after = board.clone();
+            after.make_move(*action);
+            assert_eq!(board.gives_check(*action), after.in_check(), "{action}");
+        }
+    }
+
+    #[test]
+    fn only_unpromoted_pieces_below_gold_can_promote() {
+        // every piece that can't promote, standing in the zone with room to move
+        for piece in ["+P", "+L", "+N", "+S", "+B", "+R", "G"] {
+            let board = from_sfen(&format!("8k/9/4{piece}4/9/9/9/9/9/K8 b - 1"));
+            assert!(
+                !board.get_actions().iter().any(|action| action.is_promo()),
+                "{piece}"
+            );
+        }
+        let board = from_sfen("9/9/4K4/9/9/9/9/9/k8 b - 1");
+        assert!(!board.get_actions().iter().any(|action| action.is_promo()));
+
+        // a silver can always turn the promotion down, moving into, inside or out of the zone
+        let board = from_sfen("8k/9/9/4S4/9/9/9/9/K8 b - 1");
+        assert_eq!(
+            moves_from(&board, "5d"),
+            ["5d4c", "5d4c+", "5d4e", "5d5c", "5d5c+", "5d6c", "5d6c+", "5d6e"]
+        );
+        let board = from_sfen("8k/9/4S4/9/9/9/9/9/K8 b - 1");
+        assert_eq!(
+            moves_from(&board, "5c"),
+            ["5c4b", "5c4b+", "5c4d", "5c4d+", "5c5b", "5c5b+", "5c6b", "5c6b+", "5c6d", "5c6d+"]
+        );
+    }
+
+    fn drop_targets(board: &Board, piece: Piece) -> Bitboard {
+        board
+            .get_drops()
+            .iter()
+            .filter(|action| action.piece().piece() == piece)
+            .map(|action| action.to())
+            .collect()
+    }
+
+    #[test]
+    fn drops_stay_off_the_ranks_they_could_never_leave() {
+        let board = from_sfen("k8/9/9/9/9/9/9/9/8K b L 1");
+        let lance = drop_targets(&board, Piece::LANCE);
+        assert!((lance & Bitboard::from_rank(8)).is_empty());
+        // everything outside the last rank but the king's square
+        assert_eq!(lance.popcount(), 72 - 1);
+        assert!(lance.contains(Square::from_usi("5b").expect("bad square")));
+
+        let board = from_sfen("k8/9/9/9/9/9/9/9/8K w n 1");
+        let knight = drop_targets(&board, Piece::KNIGHT);
+        assert!((knight & (Bitboard::from_rank(0) | Bitboard::from_rank(1))).is_empty());
+        assert_eq!(knight.popcount(), 63 - 1);
+        assert!(knight.contains(Square::from_usi("5g").expect("bad square")));
+    }
+
+    #[test]
+    fn the_fourth_occurrence_is_a_draw() {
+        let mut board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b - 1");
+        let cycle = ["5i4i", "5a4a", "4i5i", "4a5a"];
+        for _ in 0..2 {
+            play(&mut board, &cycle);
+        }
+        play(&mut board, &cycle[..3]);
+        assert!(!board.is_draw());
+        play(&mut board, &cycle[3..]);
+        assert!(board.is_draw());
+        // and it stops being one once the move is taken back
+        board.unmake_move();
+        assert!(!board.is_draw());
+    }
+
+    // the slow way round, every square of the mailbox and every hand slot
+    fn mailbox_material(state: &Position, side: Side) -> i32 {
+        let board: i32 = (0..NUM_SQUARES)
+            .map(|sq| state.piece_on_square(Square(sq)))
+            .filter(|piece| *piece != Piece::NONE && piece.side() == side.as_u8())
+            .map(|piece| PIECE_VALUES[piece.piece().as_usize()])
+            .sum();
+        let hand: i32 = (0..7)
+            .map(|piece| {
+                PIECE_VALUES[piece]
+                    * i32::from(state.hands[side.as_usize()].count(Piece(piece as u8)))
+            })
+            .sum();
+        board + hand
+    }
+
+    #[test]
+    fn material_agrees_with_a_mailbox_scan() {
+        let mut state = 0x4D41_5445;
+        for _ in 0..500 {
+            let board = crate::fuzz::random_position(&mut state);
+            let position = board.current_position();
+            for side in Side::ALL {
+                assert_eq!(
+                    position.material(side),
+                    mailbox_material(position, side),
+                    "{}",
+                    board.to_sfen()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn building_startpos_by_hand_matches_loading_it() {
+        let loaded = from_sfen(STARTPOS_SFEN);
+        let mut built = Board::default();
+        // a fresh board is at move 1 like any other way of starting one
+        assert_eq!(built.ply(), 1);
+        built.set_piece(Square(0), Piece::GOLD);
+        built
+            .set_hand(Side::Gote, Piece::PAWN, 3)
+            .expect("hand refused");
+        built.clear();
+        for sq in loaded.current_position().occupied() {
+            built.set_piece(sq, loaded.piece_on_square(sq));
+        }
+        assert_eq!(built.current_position(), loaded.current_position());
+        assert_eq!(built.hash(), loaded.hash());
+        assert_eq!(built.to_sfen(), STARTPOS_SFEN);
+
+        // and the edits keep the hash in step as they go
+        built.remove_piece(Square::from_usi("7g").expect("bad square"));
+        built
+            .set_hand(Side::Sente, Piece::PAWN, 1)
+            .expect("hand refused");
+        let edited = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PP1PPPPPP/1B5R1/LNSGKGSNL b P 1";
+        assert_eq!(built.hash(), from_sfen(edited).hash());
+        assert_eq!(
+            built.set_hand(Side::Sente, Piece::KING, 1),
+            Err(FenError::InvalidCharacter('K'))
+        );
+    }
+
+    #[test]
+    fn each_side_needs_exactly_one_king() {
+        let mut board = Board::default();
+        assert_eq!(board.try_load_fen("4k4/9/9/9/9/9/9/9/4K4 b - 1"), Ok(()));
+        for (sfen, side) in [
+            ("4k4/9/9/9/9/9/9/9/9 b - 1", Side::Sente),
+            ("9/9/9/9/9/9/9/9/4K4 b - 1", Side::Gote),
+            ("4k4/9/9/9/9/9/9/9/3KK4 b - 1", Side::Sente),
+            ("3kk4/9/9/9/9/9/9/9/4K4 b - 1", Side::Gote),
+        ] {
+            assert_eq!(
+                board.try_load_fen(sfen),
+                Err(FenError::IllegalKingCount(side)),
+                "{sfen}"
+            );
+        }
+
+        // tsume mode only lets the attacker go without one
+        board.set_tsume_mode(true);
+        assert_eq!(board.try_load_fen("4k4/9/9/9/9/9/9/9/9 b G 1"), Ok(()));
+        assert_eq!(
+            board.try_load_fen("4k4/9/9/9/9/9/9/9/9 w g 1"),
+            Err(FenError::IllegalKingCount(Side::Sente))
+        );
+        assert_eq!(
+            board.try_load_fen("4k4/9/9/9/9/9/9/9/3KK4 b - 1"),
+            Err(FenError::IllegalKingCount(Side::Sente))
+        );
+    }
+
+    #[test]
+    fn rotating_startpos_swaps_the_sides() {
+        let board = from_sfen(STARTPOS_SFEN);
+        let state = board.current_position();
+        let sente = state.sides[Side::Sente.as_usize()];
+        let gote = state.sides[Side::Gote.as_usize()];
+        assert_eq!(sente.rotate180().rotate180(), sente);
+        assert_eq!(state.occupied().rotate180().rotate180(), state.occupied());
+        // the starting position is symmetric, so turning it around swaps whose pieces are where
+        assert_eq!(sente.rotate180(), gote);
+        for piece in 0..NUM_PIECE_TYPES {
+            assert_eq!(
+                state.sided_piece(piece, Side::Sente).rotate180(),
+                state.sided_piece(piece, Side::Gote)
+            );
+        }
+    }
+
+    #[test]
+    fn the_move_number_counts_every_move() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        assert_eq!(board.move_number(), 1);
+        play(&mut board, &["7g7f"]);
+        assert_eq!(board.move_number(), 2);
+        assert!(board.to_sfen().ends_with(" w - 2"));
+        play(&mut board, &["3c3d"]);
+        assert!(board.to_sfen().ends_with(" b - 3"));
+        board.unmake_move();
+        assert_eq!(board.move_number(), 2);
+    }
+
+    #[test]
+    fn nifu_is_only_about_unpromoted_pawns() {
+        // two tokins and a pawn on one file are fine
+        let board = from_sfen("4k4/9/4+P4/9/4+P4/9/4P4/9/4K4 b - 1");
+        assert!(!board.current_position().has_nifu(Side::Sente));
+        // a pawn each on the same file is fine too
+        let board = from_sfen("4k4/9/4p4/9/9/9/4P4/9/4K4 b - 1");
+        assert!(!board.current_position().has_nifu(Side::Sente));
+        assert!(!board.current_position().has_nifu(Side::Gote));
+
+        let mut board = Board::default();
+        assert_eq!(
+            board.try_load_fen("4k4/9/9/4P4/9/9/4P4/9/4K4 b - 1"),
+            Err(FenError::Nifu(Side::Sente))
+        );
+        assert_eq!(
+            board.try_load_fen("4k4/9/p8/9/p8/9/9/9/4K4 b - 1"),
+            Err(FenError::Nifu(Side::Gote))
+        );
+    }
+
+    #[test]
+    fn piece_counts_match_each_bitboards_popcount() {
+        let mut state = 0x434F_554E;
+        for _ in 0..200 {
+            let board = crate::fuzz::random_position(&mut state);
+            let position = board.current_position();
+            let counts = position.piece_counts();
+            for piece in 0..NUM_PIECE_TYPES {
+                let per_side: u32 = Side::ALL
+                    .iter()
+                    .map(|side| position.sided_piece(piece, *side).popcount())
+                    .sum();
+                assert_eq!(counts[piece as usize], per_side, "{}", board.to_sfen());
+            }
+            assert_eq!(counts.iter().sum::<u32>(), position.occupied().popcount());
+        }
+        let startpos = from_sfen(STARTPOS_SFEN).current_position().piece_counts();
+        assert_eq!(startpos[..8], [18, 4, 4, 4, 2, 2, 4, 2]);
+    }
+
+    #[test]
+    fn startpos_moves_are_all_quiet() {
+        let board = from_sfen(STARTPOS_SFEN);
+        let quiets = board.get_quiets();
+        assert_eq!(quiets.len(), board.get_actions().len());
+        assert!(quiets.iter().all(|action| board.is_quiet(*action)));
+        assert!(board.get_captures().is_empty());
+
+        // a capture and a promotion drop out, the drops stay in
+        let board = from_sfen("4k4/9/4p4/4P4/9/9/9/9/4K4 b G 1");
+        let quiets = board.get_quiets();
+        assert!(!quiets.contains(&mv("5d", "5c")));
+        assert!(quiets.iter().any(|action| action.is_drop()));
+        assert!(quiets.iter().all(|action| !action.is_promo()));
+        assert_eq!(
+            quiets.len() + board.get_captures().len(),
+            board.get_actions().len()
+        );
+    }
+
+    #[test]
+    fn a_reused_list_matches_fresh_generation() {
+        let mut list = Actionlist::new();
+        for sfen in [
+            STARTPOS_SFEN,
+            "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1",
+            "4k4/9/9/9/9/9/9/9/4K4 b - 1",
+        ] {
+            let board = from_sfen(sfen);
+            board.append_actions(&mut list);
+            assert_eq!(list, board.get_actions(), "{sfen}");
+        }
+    }
+
+    #[test]
+    fn declaring_needs_ten_pieces_the_points_and_no_check() {
+        // the king in the zone with ten pieces and exactly 28 points counting the hand
+        let board = from_sfen("GGGG1SSSS/R3K3B/9/9/4k4/9/9/9/9 b RB 1");
+        assert!(board.can_declare_win(Side::Sente));
+        assert!(!board.can_declare_win(Side::Gote));
+
+        // one silver in hand instead of the zone keeps the points but not the count
+        let board = from_sfen("GGGG2SSS/R3K3B/9/9/4k4/9/9/9/9 b RBS 1");
+        assert!(!board.can_declare_win(Side::Sente));
+
+        // a tokin for a silver still makes 28, but gote's silver is checking the king
+        let board = from_sfen("GGGG1+PSSS/R3K3B/5s3/9/4k4/9/9/9/9 b RB 1");
+        assert!(board.is_in_check());
+        assert!(!board.can_declare_win(Side::Sente));
+        let unchecked = from_sfen("GGGG1+PSSS/R3K3B/9/5s3/4k4/9/9/9/9 b RB 1");
+        assert!(unchecked.can_declare_win(Side::Sente));
+    }
+
+    #[test]
+    fn position_keys_ignore_how_the_position_was_reached() {
+        let mut first = from_sfen(STARTPOS_SFEN);
+        play(&mut first, &["7g7f", "3c3d", "2g2f", "8c8d"]);
+        let mut second = from_sfen(STARTPOS_SFEN);
+        play(&mut second, &["2g2f", "8c8d", "7g7f", "3c3d"]);
+        assert_eq!(first.position_key(), second.position_key());
+
+        // or the move number it's at
+        let sfen = first.to_sfen();
+        let renumbered = format!("{} 91", sfen.strip_suffix(" 5").expect("not at move 5"));
+        assert_eq!(from_sfen(&renumbered).position_key(), first.position_key());
+
+        // but the side to move and the hands are part of it
+        assert_ne!(
+            from_sfen("4k4/9/9/9/9/9/9/9/4K4 b P 1").position_key(),
+            from_sfen("4k4/9/9/9/9/9/9/9/4K4 w P 1").position_key()
+        );
+        assert_ne!(
+            from_sfen("4k4/9/9/9/9/9/9/9/4K4 b P 1").position_key(),
+            from_sfen("4k4/9/9/9/9/9/9/9/4K4 b p 1").position_key()
+        );
+    }
+
+    #[test]
+    fn hand_counts_can_run_to_two_digits() {
+        let board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b 10P 1");
+        assert_eq!(board.hand(Side::Sente).count(Piece::PAWN), 10);
+        let board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 w 2G3S18p 1");
+        assert_eq!(board.hand(Side::Sente).count(Piece::GOLD), 2);
+        assert_eq!(board.hand(Side::Sente).count(Piece::SILVER), 3);
+        assert_eq!(board.hand(Side::Sente).total(), 5);
+        assert_eq!(board.hand(Side::Gote).count(Piece::PAWN), 18);
+
+        let mut board = Board::default();
+        assert_eq!(
+            board.try_load_fen("4k4/9/9/9/9/9/9/9/4K4 b 32P 1"),
+            Err(FenError::HandOverflow('P'))
+        );
+    }
+
+    #[test]
+    fn is_legal_agrees_with_making_the_move() {
+        let mut state = 0x4C45_4741;
+        let mut checked = 0;
+        while checked < 300 {
+            let board = crate::fuzz::random_position(&mut state);
+            let position = board.current_position();
+            let mover = board.stm();
+            // the side that just moved can't have left its king en prise
+            let their_king = position.king_square(mover.flip());
+            if board.attackers_to(their_king, mover).is_not_empty() {
+                continue;
+            }
+            checked += 1;
+
+            for action in &board.get_actions() {
+                let mut after = board.clone();
+                after.make_move(*action);
+                let king = after.current_position().king_square(mover);
+                let safe = after.attackers_to(king, mover.flip()).is_empty();
+                assert_eq!(
+                    board.is_legal(*action),
+                    safe,
+                    "{action} in {}",
+                    board.to_sfen()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn games_past_the_ply_cap_are_drawn() {
+        let mut board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b - 1");
+        board.set_max_ply(4);
+        // shuffling the kings sideways so nothing repeats before the cap
+        for (index, usi) in ["5i4i", "5a4a", "4i3i", "4a3a"].iter().enumerate() {
+            assert_eq!(board.game_result(), None, "ended early at move {index}");
+            play(&mut board, &[usi]);
+        }
+        assert_eq!(board.ply(), 5);
+        assert_eq!(board.game_result(), Some(GameResult::MaxPly));
+    }
+
+    #[test]
+    fn attack_maps_stop_at_the_first_blocker() {
+        let board = from_sfen("l3k4/9/9/p8/4+R2p1/9/4P3P/9/4K3L b - 1");
+        let attacks = |usi: &str| board.attacks_from(Square::from_usi(usi).expect("bad square"));
+
+        // the dragon runs into the gote king, its own pawn and the gote pawn, and adds the diagonal steps
+        assert_eq!(
+            attacks("5e"),
+            squares(&[
+                "5d", "5c", "5b", "5a", "5f", "5g", "6e", "7e", "8e", "9e", "4e", "3e", "2e", "4d",
+                "6d", "4f", "6f"
+            ])
+        );
+        // lances of either side stop on the piece in front, whoever owns it
+        assert_eq!(attacks("1i"), squares(&["1h", "1g"]));
+        assert_eq!(attacks("9a"), squares(&["9b", "9c", "9d"]));
+        assert_eq!(attacks("5h"), Bitboard::EMPTY);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn boards_survive_a_json_round_trip() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        play(&mut board, &["7g7f", "3c3d", "8h2b+", "3a2b", "B*4e"]);
+        let json = serde_json::to_string(&board).expect("board didn't serialize");
+        let mut loaded: Board = serde_json::from_str(&json).expect("board didn't deserialize");
+
+        assert_eq!(loaded.history_len(), board.history_len());
+        for (loaded, state) in loaded.states.iter().zip(&board.states) {
+            assert_eq!(loaded, state);
+            assert_eq!(loaded.hash, state.hash);
+            assert_eq!(loaded.checkers, state.checkers);
+            assert_eq!(loaded.halfmove_clock, state.halfmove_clock);
+        }
+        assert_eq!(loaded.stm(), board.stm());
+        assert_eq!(loaded.ply(), board.ply());
+        assert_eq!(loaded.to_sfen(), board.to_sfen());
+        assert_eq!(
+            sorted_usi(&loaded.get_legal_actions()),
+            sorted_usi(&board.get_legal_actions())
+        );
+    }
+
+    #[test]
+    fn going_back_to_a_ply_matches_playing_up_to_it() {
+        let mut one_move = from_sfen(STARTPOS_SFEN);
+        play(&mut one_move, &["7g7f"]);
+        let mut board = from_sfen(STARTPOS_SFEN);
+        play(&mut board, &["7g7f", "3c3d", "8h2b+"]);
+        assert_eq!(board.history_len(), 4);
+
+        board.goto_ply(1);
+        assert_eq!(board.history_len(), 2);
+        assert_eq!(board.current_position(), one_move.current_position());
+        assert_eq!(board.hash(), one_move.hash());
+        assert_eq!(board.stm(), one_move.stm());
+        assert_eq!(board.ply(), one_move.ply());
+        assert_eq!(board.to_sfen(), one_move.to_sfen());
+
+        // the takeback leaves a board that plays on normally
+        play(&mut board, &["8c8d"]);
+        play(&mut one_move, &["8c8d"]);
+        assert_eq!(board.hash(), one_move.hash());
+    }
+
+    #[test]
+    fn the_builder_checks_the_position_it_builds() {
+        let sq = |usi: &str| Square::from_usi(usi).expect("bad square");
+        let gote = Side::Gote.as_u8();
+        let kings = || {
+            BoardBuilder::default()
+                .place(sq("5i"), Piece::KING)
+                .place(sq("5a"), Piece::KING.as_stm(gote))
+        };
+
+        let built = kings()
+            .place(sq("7g"), Piece::PAWN)
+            .place(sq("2b"), Piece::BISHOP.promote().as_stm(gote))
+            .hand(Side::Sente, Piece::SILVER, 2)
+            .hand(Side::Gote, Piece::PAWN, 1)
+            .side_to_move(Side::Gote)
+            .build()
+            .expect("valid position refused");
+        let loaded = from_sfen("4k4/7+b1/9/9/9/9/2P6/9/4K4 w 2Sp 1");
+        assert_eq!(built.current_position(), loaded.current_position());
+        assert_eq!(built.hash(), loaded.hash());
+        assert_eq!(built.stm(), Side::Gote);
+        assert_eq!(built.ply(), 1);
+
+        let nifu = kings()
+            .place(sq("3g"), Piece::PAWN.as_stm(gote))
+            .place(sq("3c"), Piece::PAWN.as_stm(gote))
+            .build();
+        assert_eq!(nifu.map(|_| ()), Err(FenError::Nifu(Side::Gote)));
+        // a tokin next to a pawn on the same file is fine
+        assert!(kings()
+            .place(sq("3g"), Piece::PAWN)
+            .place(sq("3c"), Piece::PROMO_PAWN)
+            .build()
+            .is_ok());
+        assert_eq!(
+            kings().place(sq("1a"), Piece::PAWN).build().map(|_| ()),
+            Err(FenError::StuckPiece(sq("1a")))
+        );
+        assert_eq!(
+            BoardBuilder::default()
+                .place(sq("5i"), Piece::KING)
+                .build()
+                .map(|_| ()),
+            Err(FenError::IllegalKingCount(Side::Gote))
+        );
+    }
+
+    #[test]
+    fn only_pawn_drops_are_barred_from_mating() {
+        // pushing a pawn in for mate is fine, promoting or not
+        let mut board = from_sfen("7nk/9/7GP/9/9/9/9/9/4K4 b P 1");
+        let legal = sorted_usi(&board.get_legal_actions());
+        assert!(legal.contains(&"1c1b+".to_string()) && legal.contains(&"1c1b".to_string()));
+        assert!(!legal.contains(&"P*1b".to_string()));
+        let mut pushed = board.clone();
+        play(&mut board, &["1c1b+"]);
+        play(&mut pushed, &["1c1b"]);
+        assert_eq!(board.game_result(), Some(GameResult::Checkmate));
+        assert_eq!(pushed.game_result(), Some(GameResult::Checkmate));
+
+        // without the knight the king gets out to 2a, so the same drop is only a check
+        let mut board = from_sfen("8k/9/7G1/9/9/9/9/9/4K4 b P 1");
+        let drop = board
+            .action_from_usi("P*1b")
+            .expect("checking drop refused");
+        assert!(board.get_legal_actions().contains(&drop));
+        board.make_move(drop);
+        assert!(board.in_check());
+        assert_eq!(sorted_usi(&board.get_legal_actions()), ["1a2a"]);
+    }
+
+    #[test]
+    fn accessors_follow_the_game() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        assert_eq!(
+            (board.stm(), board.side_to_move(), board.ply()),
+            (Side::Sente, 0, 1)
+        );
+        play(&mut board, &["7g7f", "3c3d", "8h2b+"]);
+        assert_eq!(
+            (board.stm(), board.side_to_move(), board.ply()),
+            (Side::Gote, 1, 4)
+        );
+
+        let position = board.current_position();
+        let horse = Square::from_usi("2b").expect("bad square");
+        assert_eq!(position.piece_on_square(horse), Piece::PROMO_BISHOP);
+        assert!(position.occupied().contains(horse));
+        assert_eq!(position.occupied().popcount(), 39);
+        assert_eq!(board.hand(Side::Sente).count(Piece::BISHOP), 1);
+    }
+
+    #[test]
+    fn captured_pieces_come_off_the_target_square() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        let quiet = board.action_from_usi("7g7f").expect("illegal move");
+        assert_eq!(board.captured_piece(quiet), Piece::NONE);
+        play(&mut board, &["7g7f", "3c3d"]);
+
+        let capture = board.action_from_usi("8h2b+").expect("illegal move");
+        assert_eq!(
+            board.captured_piece(capture),
+            Piece::BISHOP.as_stm(Side::Gote.as_u8())
+        );
+        play(&mut board, &["8h2b+", "3a2b"]);
+
+        let drop = board.action_from_usi("B*5e").expect("illegal drop");
+        assert_eq!(board.captured_piece(drop), Piece::NONE);
+    }
+
+    #[test]
+    fn the_halfmove_clock_resets_on_captures_and_pawn_moves() {
+        let mut board = from_sfen(STARTPOS_SFEN);
+        let mut clocks = Vec::new();
+        for usi in [
+            "7i7h", "3a4b", "7g7f", "3c3d", "5i5h", "5a5b", "8h2b+", "4b3a", "2b3a", "5b6b",
+        ] {
+            play(&mut board, &[usi]);
+            clocks.push(board.halfmove_clock());
+        }
+        // quiet moves count up, a pawn move, a capture or a recapture starts it again
+        assert_eq!(clocks, [1, 2, 0, 0, 1, 2, 0, 1, 0, 1]);
+
+        board.unmake_move();
+        assert_eq!(board.halfmove_clock(), 0);
+        let mut board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 b P 1");
+        play(&mut board, &["5i4i", "5a4a", "P*5e"]);
+        assert_eq!(board.halfmove_clock(), 0);
+    }
+
+    #[test]
+    fn a_pinned_rook_keeps_to_the_file_and_its_promotions() {
+        let board = from_sfen("4r3k/9/9/9/4R4/9/9/9/4K4 b - 1");
+        let rook = Square::from_usi("5e").expect("bad square");
+        assert_eq!(
+            sorted_usi(&board.legal_moves_from(rook)),
+            ["5e5a", "5e5a+", "5e5b", "5e5b+", "5e5c", "5e5c+", "5e5d", "5e5f", "5e5g", "5e5h"]
+        );
+        // unpinned it would have the whole rank as well
+        assert!(moves_from(&board, "5e").contains(&"5e4e".to_string()));
+
+        // empty squares and the opponent's pieces have nothing to offer
+        assert!(board
+            .legal_moves_from(Square::from_usi("5f").expect("bad square"))
+            .is_empty());
+        assert!(board
+            .legal_moves_from(Square::from_usi("5a").expect("bad square"))
+            .is_empty());
     }
 }
@@ -0,0 +1,293 @@
+use crate::{
+    board::{Board, BoardBuilder, FenError},
+    types::{
+        action::Action,
+        piece::Piece,
+        side::Side,
+        square::{Square, BOARD_LEN, NUM_SQUARES},
+    },
+};
+
+// why a csa record couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsaError {
+    BadLine(String),
+    BadPiece(String),
+    BadSquare(String),
+    // no + or - line saying who moves first
+    MissingSide,
+    // the position was read but didn't make a valid board
+    InvalidPosition(FenError),
+    IllegalMove(String),
+}
+
+// how many of each piece type a full set has, indexed by piece type
+const PIECE_COUNTS: [u32; 8] = [18, 4, 4, 4, 2, 2, 4, 2];
+
+fn parse_piece(code: &str) -> Result<Piece, CsaError> {
+    Ok(match code {
+        "FU" => Piece::PAWN,
+        "KY" => Piece::LANCE,
+        "KE" => Piece::KNIGHT,
+        "GI" => Piece::SILVER,
+        "KI" => Piece::GOLD,
+        "KA" => Piece::BISHOP,
+        "HI" => Piece::ROOK,
+        "OU" => Piece::KING,
+        "TO" => Piece::PROMO_PAWN,
+        "NY" => Piece::PROMO_LANCE,
+        "NK" => Piece::PROMO_KNIGHT,
+        "NG" => Piece::PROMO_SILVER,
+        "UM" => Piece::PROMO_BISHOP,
+        "RY" => Piece::PROMO_ROOK,
+        _ => return Err(CsaError::BadPiece(code.to_owned())),
+    })
+}
+
+fn parse_side(c: u8) -> Option<Side> {
+    match c {
+        b'+' => Some(Side::Sente),
+        b'-' => Some(Side::Gote),
+        _ => None,
+    }
+}
+
+// csa squares are a file digit then a rank digit, both counting 1..9 like usi, 00 is the hand
+fn parse_square(file: u8, rank: u8) -> Option<Square> {
+    if !(b'1'..=b'9').contains(&file) || !(b'1'..=b'9').contains(&rank) {
+        return None;
+    }
+    Some(Square::from_rf(
+        BOARD_LEN - (rank - b'0'),
+        BOARD_LEN - (file - b'0'),
+    ))
+}
+
+// reads the position part of a csa record: PI or P1..P9, any P+/P- lines and the side to move,
+// headers, comments and moves are skipped
+pub fn parse_csa_position(s: &str) -> Result<Board, CsaError> {
+    let mut mailbox = [Piece::NONE; NUM_SQUARES as usize];
+    let mut hands = [[0u32; 7]; 2];
+    let mut stm = None;
+
+    for line in s.lines() {
+        let line = line.trim_end();
+        let bytes = line.as_bytes();
+        if line == "+" || line == "-" {
+            stm = parse_side(bytes[0]);
+        } else if let Some(removed) = line.strip_prefix("PI") {
+            // the starting position, with handicap pieces taken off after it
+            let mut board = Board::default();
+            board.set_startpos();
+            for (i, sq) in mailbox.iter_mut().enumerate() {
+                *sq = board.piece_on_square(Square(i as u8));
+            }
+            for chunk in removed.as_bytes().chunks(4) {
+                let &[file, rank, a, b] = chunk else {
+                    return Err(CsaError::BadLine(line.to_owned()));
+                };
+                let sq =
+                    parse_square(file, rank).ok_or_else(|| CsaError::BadSquare(line.to_owned()))?;
+                parse_piece(std::str::from_utf8(&[a, b]).unwrap_or(""))?;
+                mailbox[sq.as_usize()] = Piece::NONE;
+            }
+        } else if bytes.len() >= 2 && bytes[0] == b'P' && (b'1'..=b'9').contains(&bytes[1]) {
+            // one rank, files 9 to 1 in 3 character cells, some writers trim the trailing space
+            let rank = BOARD_LEN - (bytes[1] - b'0');
+            let cells = format!("{:<27}", &line[2..]);
+            if cells.len() != 3 * BOARD_LEN as usize || !cells.is_ascii() {
+                return Err(CsaError::BadLine(line.to_owned()));
+            }
+            for file in 0..BOARD_LEN {
+                let cell = &cells[3 * file as usize..3 * file as usize + 3];
+                let sq = Square::from_rf(rank, file);
+                mailbox[sq.as_usize()] = if cell.trim() == "*" {
+                    Piece::NONE
+                } else {
+                    let side = parse_side(cell.as_bytes()[0])
+                        .ok_or_else(|| CsaError::BadPiece(cell.to_owned()))?;
+                    parse_piece(&cell[1..])?.as_stm(side.as_u8())
+                };
+            }
+        } else if let Some(side) = line
+            .strip_prefix('P')
+            .and_then(|rest| parse_side(*rest.as_bytes().first()?))
+        {
+            // pieces placed one at a time, 00 puts them in hand and AL hands out everything left over
+            for chunk in line.as_bytes()[2..].chunks(4) {
+                let &[file, rank, a, b] = chunk else {
+                    return Err(CsaError::BadLine(line.to_owned()));
+                };
+                let code = std::str::from_utf8(&[a, b]).unwrap_or("").to_owned();
+                if [file, rank] == *b"00" && code == "AL" {
+                    for piece in 0..7u8 {
+                        let on_board = mailbox
+                            .iter()
+                            .filter(|p| **p != Piece::NONE && p.piece().unpromote() == Piece(piece))
+                            .count() as u32;
+                        let held = hands[0][piece as usize] + hands[1][piece as usize];
+                        hands[side.as_usize()][piece as usize] +=
+                            PIECE_COUNTS[piece as usize].saturating_sub(on_board + held);
+                    }
+                } else if [file, rank] == *b"00" {
+                    let piece = parse_piece(&code)?;
+                    if piece.raw() >= Piece::KING.raw() {
+                        return Err(CsaError::BadPiece(code));
+                    }
+                    hands[side.as_usize()][piece.as_usize()] += 1;
+                } else {
+                    let sq = parse_square(file, rank)
+                        .ok_or_else(|| CsaError::BadSquare(line.to_owned()))?;
+                    mailbox[sq.as_usize()] = parse_piece(&code)?.as_stm(side.as_u8());
+                }
+            }
+        }
+    }
+
+    let stm = stm.ok_or(CsaError::MissingSide)?;
+
+    // built the same way as any position set up from code, with the same checks as an sfen
+    let mut builder = BoardBuilder::default().side_to_move(stm);
+    for (i, piece) in mailbox.into_iter().enumerate() {
+        if piece != Piece::NONE {
+            builder = builder.place(Square(i as u8), piece);
+        }
+    }
+    for side in Side::ALL {
+        for (piece, &count) in hands[side.as_usize()].iter().enumerate() {
+            builder = builder.hand(side, Piece(piece as u8), count);
+        }
+    }
+    builder.build().map_err(CsaError::InvalidPosition)
+}
+
+// a move like +7776FU, the piece code is what the piece is after moving so a change means a promotion
+pub fn parse_csa_move(board: &Board, s: &str) -> Result<Action, CsaError> {
+    let illegal = || CsaError::IllegalMove(s.to_owned());
+    let bytes = s.as_bytes();
+    if bytes.len() < 7 || !s.is_ascii() || parse_side(bytes[0]) != Some(board.stm()) {
+        return Err(illegal());
+    }
+    let to = parse_square(bytes[3], bytes[4]).ok_or_else(|| CsaError::BadSquare(s.to_owned()))?;
+    let piece = parse_piece(&s[5..7])?;
+
+    // converted to usi so action_from_usi can turn away anything that isn't legal here
+    let usi = if &s[1..3] == "00" {
+        format!("{}*{}", piece.as_stm(0), to)
+    } else {
+        let from =
+            parse_square(bytes[1], bytes[2]).ok_or_else(|| CsaError::BadSquare(s.to_owned()))?;
+        let moved = board.piece_on_square(from).piece();
+        if moved == piece {
+            format!("{from}{to}")
+        } else if moved.raw() < Piece::GOLD.raw() && moved.promote() == piece {
+            format!("{from}{to}+")
+        } else {
+            return Err(illegal());
+        }
+    };
+    board.action_from_usi(&usi).ok_or_else(illegal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::STARTPOS_SFEN;
+
+    fn from_sfen(sfen: &str) -> Board {
+        let mut board = Board::default();
+        board.load_fen(sfen);
+        board
+    }
+
+    const SNIPPET: &str = "\
+V2.2
+N+sente
+N-gote
+P1 *  *  *  * -OU *  *  *  *
+P2 *  *  *  *  *  *  *  *  *
+P3 *  *  *  * +FU *  *  *  *
+P4 *  *  *  *  *  *  *  *  *
+P5 *  *  *  *  *  *  *  *  *
+P6 *  *  *  *  *  *  *  *  *
+P7 *  *  *  *  *  *  *  *  *
+P8 *  *  *  *  *  *  *  *  *
+P9 *  *  *  * +OU *  *  *  *
+P+00KI
++
+";
+
+    #[test]
+    fn a_snippet_loads_like_its_sfen() {
+        let board = parse_csa_position(SNIPPET).expect("snippet refused");
+        let expected = from_sfen("4k4/9/4P4/9/9/9/9/9/4K4 b G 1");
+        assert_eq!(board.current_position(), expected.current_position());
+        assert_eq!(board.stm(), expected.stm());
+        assert_eq!(board.hash(), expected.hash());
+
+        let startpos = parse_csa_position("PI\n+\n").expect("PI refused");
+        assert_eq!(startpos.to_sfen(), STARTPOS_SFEN);
+        // a handicap takes pieces off the starting position, a bishop here
+        let handicap = parse_csa_position("PI22KA\n-\n").expect("handicap refused");
+        assert_eq!(
+            handicap.to_sfen(),
+            "lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"
+        );
+    }
+
+    #[test]
+    fn al_hands_out_everything_left() {
+        let board = parse_csa_position(&format!("{SNIPPET}P-00AL\n")).expect("snippet refused");
+        let expected = from_sfen("4k4/9/4P4/9/9/9/9/9/4K4 b G2r2b3g4s4n4l17p 1");
+        assert_eq!(board.current_position(), expected.current_position());
+    }
+
+    #[test]
+    fn bad_positions_are_reported() {
+        assert_eq!(
+            parse_csa_position(SNIPPET.trim_end_matches("+\n")).err(),
+            Some(CsaError::MissingSide)
+        );
+        // a pawn on the last rank could never move
+        let stuck = SNIPPET.replace("P3 *  *  *  * +FU", "P3 *  *  *  *  * ");
+        let stuck = stuck.replace("P1 * ", "P1+FU");
+        assert_eq!(
+            parse_csa_position(&stuck).err(),
+            Some(CsaError::InvalidPosition(FenError::StuckPiece(
+                Square::from_usi("9a").expect("bad square")
+            )))
+        );
+        assert_eq!(
+            parse_csa_position("P1+XX\n+\n").err(),
+            Some(CsaError::BadPiece("XX".to_owned()))
+        );
+        // ten files on one rank
+        let wide = format!("P1{}", " * ".repeat(10));
+        assert_eq!(
+            parse_csa_position(&format!("{wide}\n+\n")).err(),
+            Some(CsaError::BadLine(wide.trim_end().to_owned()))
+        );
+    }
+
+    #[test]
+    fn moves_decode_to_the_matching_usi() {
+        let board = parse_csa_position(SNIPPET).expect("snippet refused");
+        for (csa, usi) in [
+            ("+0052KI", "G*5b"),
+            ("+5352FU", "5c5b"),
+            ("+5352TO", "5c5b+"),
+            ("+5948OU", "5i4h"),
+        ] {
+            let action = parse_csa_move(&board, csa).expect("legal move refused");
+            assert_eq!(action.to_usi(), usi);
+        }
+        assert_eq!(
+            parse_csa_move(&board, "-5152OU"),
+            Err(CsaError::IllegalMove("-5152OU".to_owned()))
+        );
+        assert_eq!(
+            parse_csa_move(&board, "+5351FU"),
+            Err(CsaError::IllegalMove("+5351FU".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{
+    board::Board,
+    movegen::get_king_attacks,
+    types::{
+        piece::{Piece, NUM_PIECE_TYPES},
+        side::Side,
+        square::NUM_SQUARES,
+    },
+};
+
+// indexed by piece type, the king is worth nothing since it can't be traded
+pub const PIECE_VALUES: [i32; NUM_PIECE_TYPES as usize] = [
+    100,  // pawn
+    300,  // lance
+    350,  // knight
+    500,  // silver
+    800,  // bishop
+    950,  // rook
+    550,  // gold
+    0,    // king
+    520,  // tokin
+    540,  // promoted lance
+    560,  // promoted knight
+    570,  // promoted silver
+    1000, // horse
+    1150, // dragon
+];
+
+// taken off for every enemy attack on a square next to the king
+const KING_ZONE_ATTACK_PENALTY: i32 = 8;
+
+// the pieces with a square table, in the same order as PIECE_SQUARE_TABLES
+const PST_PIECES: [Piece; 4] = [Piece::PAWN, Piece::SILVER, Piece::GOLD, Piece::KING];
+
+// bonuses on top of material, from sente's side and indexed by square so the first row is rank i
+// and the left column is file 9, gote looks them up with the square flipped
+#[rustfmt::skip]
+const PIECE_SQUARE_TABLES: [[i32; NUM_SQUARES as usize]; 4] = [
+    // pawn, pushed forward but not so far it's just waiting to be taken
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,   0,
+          5,   5,   5,   8,   8,   8,   5,   5,   5,
+         10,  10,  10,  12,  12,  12,  10,  10,  10,
+         15,  15,  15,  15,  15,  15,  15,  15,  15,
+         20,  20,  20,  20,  20,  20,  20,  20,  20,
+         25,  25,  25,  25,  25,  25,  25,  25,  25,
+          0,   0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // silver, off the back rank and up next to the castle or into the centre
+    [
+        -10, -10, -10, -10, -10, -10, -10, -10, -10,
+          0,   5,  10,  10,   0,  10,  10,   5,   0,
+          5,  10,  15,  15,  10,  15,  15,  10,   5,
+          5,  10,  15,  15,  15,  15,  15,  10,   5,
+          5,  10,  15,  20,  20,  20,  15,  10,   5,
+          5,  10,  10,  15,  15,  15,  10,  10,   5,
+         10,  10,  10,  10,  10,  10,  10,  10,  10,
+          5,   5,   5,   5,   5,   5,   5,   5,   5,
+          0,   0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // gold, stays home around the king
+    [
+          0,   5,   5,  10,   5,  10,   5,   5,   0,
+          0,  10,  15,  15,  10,  15,  15,  10,   0,
+         -5,   5,  10,  10,   5,  10,  10,   5,  -5,
+        -10,  -5,   0,   0,   0,   0,   0,  -5, -10,
+        -15, -10,  -5,  -5,  -5,  -5,  -5, -10, -15,
+        -15, -10,  -5,  -5,  -5,  -5,  -5, -10, -15,
+        -10,  -5,   0,   0,   0,   0,   0,  -5, -10,
+        -10,  -5,   0,   0,   0,   0,   0,  -5, -10,
+        -10,  -5,   0,   0,   0,   0,   0,  -5, -10,
+    ],
+    // king, tucked into a castle on either wing rather than sitting in the middle
+    [
+          5,  20,  25,  10,   0,  10,  25,  20,   5,
+          0,  15,  20,   5,  -5,   5,  20,  15,   0,
+        -10,   0,   5, -10, -15, -10,   5,   0, -10,
+        -25, -20, -20, -25, -30, -25, -20, -20, -25,
+        -35, -30, -30, -35, -40, -35, -30, -30, -35,
+        -40, -35, -35, -40, -45, -40, -35, -35, -40,
+        -40, -35, -35, -40, -45, -40, -35, -35, -40,
+        -40, -35, -35, -40, -45, -40, -35, -35, -40,
+        -40, -35, -35, -40, -45, -40, -35, -35, -40,
+    ],
+];
+
+// a penalty for the enemy attacks on the king and the squares around it, 0 without a king
+pub fn king_safety(board: &Board, side: Side) -> i32 {
+    let king = board.sided_piece(Piece::KING.raw(), side);
+    if king.is_empty() {
+        return 0;
+    }
+    let king_sq = king.lsb_square();
+    let zone = get_king_attacks(king_sq) | king;
+    let attacks: u32 = zone
+        .into_iter()
+        .map(|sq| board.attackers_to(sq, side.flip()).popcount())
+        .sum();
+    -KING_ZONE_ATTACK_PENALTY * attacks as i32
+}
+
+// score from the side to move's perspective
+pub fn evaluate(board: &Board) -> i32 {
+    let mut score = 0;
+
+    // material, on the board and in hand
+    score += board.material(Side::Sente) - board.material(Side::Gote);
+
+    // piece squares
+    for (piece, table) in PST_PIECES.iter().zip(&PIECE_SQUARE_TABLES) {
+        for sq in board.sided_piece(piece.raw(), Side::Sente) {
+            score += table[sq.as_usize()];
+        }
+        for sq in board.sided_piece(piece.raw(), Side::Gote) {
+            score -= table[sq.flip().as_usize()];
+        }
+    }
+
+    score += king_safety(board, Side::Sente) - king_safety(board, Side::Gote);
+
+    if board.stm() == Side::Sente {
+        score
+    } else {
+        -score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::STARTPOS_SFEN;
+
+    fn eval_of(sfen: &str) -> i32 {
+        let mut board = Board::default();
+        board.load_fen(sfen);
+        evaluate(&board)
+    }
+
+    #[test]
+    fn startpos_is_level() {
+        assert_eq!(eval_of(STARTPOS_SFEN), 0);
+        assert_eq!(
+            eval_of("lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1"),
+            0
+        );
+    }
+
+    #[test]
+    fn score_flips_with_the_side_to_move() {
+        // a rook in hand either side of mirrored kings, nothing else differs
+        let sente = eval_of("4k4/9/9/9/9/9/9/9/4K4 b R 1");
+        assert_eq!(sente, PIECE_VALUES[Piece::ROOK.as_usize()]);
+        assert_eq!(eval_of("4k4/9/9/9/9/9/9/9/4K4 w R 1"), -sente);
+    }
+
+    #[test]
+    fn advanced_silvers_and_castled_kings_score_higher() {
+        let back_rank = eval_of("4k4/9/9/9/9/9/9/9/2S1K4 b - 1");
+        let by_the_castle = eval_of("4k4/9/9/9/9/9/9/2S6/4K4 b - 1");
+        assert!(by_the_castle > back_rank);
+        // the same for gote with the table flipped
+        let back_rank = eval_of("4k1s2/9/9/9/9/9/9/9/4K4 w - 1");
+        let by_the_castle = eval_of("4k4/6s2/9/9/9/9/9/9/4K4 w - 1");
+        assert!(by_the_castle > back_rank);
+
+        let castled = eval_of("4k4/9/9/9/9/9/9/1K7/9 b - 1");
+        let wandering = eval_of("4k4/9/9/9/4K4/9/9/9/9 b - 1");
+        assert!(castled > wandering);
+    }
+
+    fn board_of(sfen: &str) -> Board {
+        let mut board = Board::default();
+        board.load_fen(sfen);
+        board
+    }
+
+    #[test]
+    fn a_rook_aimed_at_the_king_costs_safety() {
+        let quiet = board_of("4k4/9/9/9/9/9/9/9/4K4 b - 1");
+        let aimed = board_of("4k4/9/9/9/4R4/9/9/9/4K4 b - 1");
+        assert_eq!(king_safety(&quiet, Side::Gote), 0);
+        assert!(king_safety(&aimed, Side::Gote) < king_safety(&quiet, Side::Gote));
+        // it's only the king being aimed at that pays
+        assert_eq!(
+            king_safety(&aimed, Side::Sente),
+            king_safety(&quiet, Side::Sente)
+        );
+    }
+}
@@ -0,0 +1,92 @@
+// only built for tests, random positions to check the sfen reader and writer against each other
+
+use crate::{
+    board::{Board, BoardBuilder},
+    types::{
+        piece::Piece,
+        side::Side,
+        square::{Square, NUM_SQUARES},
+    },
+    zobrist::next_key,
+};
+
+// everything but the kings, by piece type
+const PIECE_SET: [(Piece, u32); 7] = [
+    (Piece::PAWN, 18),
+    (Piece::LANCE, 4),
+    (Piece::KNIGHT, 4),
+    (Piece::SILVER, 4),
+    (Piece::GOLD, 4),
+    (Piece::BISHOP, 2),
+    (Piece::ROOK, 2),
+];
+
+// a random position out of one shogi set, each piece lands on the board for either side, possibly
+// promoted, goes in a hand, or is left out, redrawn until it passes BoardBuilder::build
+pub fn random_position(state: &mut u64) -> Board {
+    loop {
+        let mut builder = BoardBuilder::default();
+        let mut empty: Vec<Square> = (0..NUM_SQUARES).map(Square).collect();
+        let mut take_square = |state: &mut u64| {
+            let index = (next_key(state) % empty.len() as u64) as usize;
+            empty.swap_remove(index)
+        };
+
+        for side in Side::ALL {
+            builder = builder.place(take_square(state), Piece::KING.as_stm(side.as_u8()));
+        }
+        let mut hands = [[0; PIECE_SET.len()]; 2];
+        for (slot, &(piece, count)) in PIECE_SET.iter().enumerate() {
+            for _ in 0..count {
+                let roll = next_key(state);
+                let side = Side::ALL[(roll & 1) as usize];
+                match (roll >> 1) % 3 {
+                    0 => {
+                        let promoted = piece != Piece::GOLD && (roll >> 3) & 1 == 1;
+                        let piece = if promoted { piece.promote() } else { piece };
+                        builder = builder.place(take_square(state), piece.as_stm(side.as_u8()));
+                    }
+                    1 => hands[side.as_usize()][slot] += 1,
+                    _ => {}
+                }
+            }
+        }
+        for side in Side::ALL {
+            for (slot, &(piece, _)) in PIECE_SET.iter().enumerate() {
+                builder = builder.hand(side, piece, hands[side.as_usize()][slot]);
+            }
+        }
+
+        let stm = Side::ALL[(next_key(state) & 1) as usize];
+        if let Ok(board) = builder.side_to_move(stm).build() {
+            return board;
+        }
+    }
+}
+
+// loads each random position's sfen back into a fresh board and checks nothing changed
+#[test]
+fn sfen_round_trip() {
+    let mut state = 0x5346_454E;
+    for _ in 0..2000 {
+        let board = random_position(&mut state);
+        let sfen = board.to_sfen();
+
+        let mut loaded = Board::default();
+        if let Err(err) = loaded.try_load_fen(&sfen) {
+            panic!("{sfen} didn't load: {err:?}");
+        }
+        if let Err(err) = loaded.current_position().debug_validate() {
+            panic!("{sfen} loaded inconsistently: {err}");
+        }
+        assert_eq!(
+            loaded.current_position(),
+            board.current_position(),
+            "{sfen}"
+        );
+        assert_eq!(loaded.stm(), board.stm(), "{sfen}");
+        assert_eq!(loaded.ply(), board.ply(), "{sfen}");
+        assert_eq!(loaded.hash(), board.hash(), "{sfen}");
+        assert_eq!(loaded.to_sfen(), sfen);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{
+    board::Board,
+    types::{
+        action::Action,
+        piece::NUM_PIECE_TYPES,
+        square::{Square, BOARD_LEN},
+    },
+};
+
+const FILES: [char; 9] = ['１', '２', '３', '４', '５', '６', '７', '８', '９'];
+const RANKS: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+// indexed by piece type
+const PIECE_NAMES: [&str; NUM_PIECE_TYPES as usize] = [
+    "歩", "香", "桂", "銀", "角", "飛", "金", "玉", "と", "成香", "成桂", "成銀", "馬", "龍",
+];
+
+impl Board {
+    // traditional notation like ７六歩, takes the previous move's destination to write 同 for recaptures,
+    // has to be called before the action is made
+    pub fn action_to_kif(&self, action: Action, prev_to: Option<Square>) -> String {
+        let to = action.to();
+        let mut kif = if prev_to == Some(to) {
+            "同　".to_owned()
+        } else {
+            // usi files and ranks both count from 1
+            let file = BOARD_LEN - to.file();
+            let rank = BOARD_LEN - to.rank();
+            format!("{}{}", FILES[file as usize - 1], RANKS[rank as usize - 1])
+        };
+
+        let piece = if action.is_drop() {
+            action.piece()
+        } else {
+            self.piece_on_square(action.from())
+        };
+        kif += PIECE_NAMES[piece.piece().as_usize()];
+
+        if action.is_drop() {
+            kif += "打";
+        } else if action.is_promo() {
+            kif += "成";
+        }
+        kif
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::STARTPOS_SFEN;
+
+    #[test]
+    fn kif_covers_moves_promotions_recaptures_and_drops() {
+        let mut board = Board::default();
+        board.load_fen(STARTPOS_SFEN);
+        let mut prev_to = None;
+        let mut kif = Vec::new();
+        for usi in ["7g7f", "3c3d", "8h2b+", "3a2b", "B*4e"] {
+            let action = board.action_from_usi(usi).expect("illegal move");
+            kif.push(board.action_to_kif(action, prev_to));
+            board.make_move(action);
+            prev_to = Some(action.to());
+        }
+        assert_eq!(kif, ["７六歩", "３四歩", "２二角成", "同　銀", "４五角打"]);
+    }
+}
@@ -0,0 +1,14 @@
+pub mod board;
+pub mod csa;
+pub mod eval;
+#[cfg(test)]
+mod fuzz;
+pub mod kif;
+pub mod movegen;
+pub mod perft;
+pub mod search;
+pub mod tsume;
+pub mod tt;
+pub mod types;
+pub mod usi;
+pub mod zobrist;
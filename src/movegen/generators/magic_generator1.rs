@@ -46,7 +46,7 @@ fn main() {
                 let mut table = [Bitboard::EMPTY; LANCE_BLOCKERS];
                 let num = Bitboard(next!(gen) & next!(gen)) & Bitboard::FULL;
                 let mut right = true;
-                for blocker_id in 1..LANCE_BLOCKERS {
+                for blocker_id in 0..LANCE_BLOCKERS {
                     let blockers = tuples[blocker_id].0;
                     let real = tuples[blocker_id].1;
                     let idx = ((blockers.0.overflowing_mul(num.0)).0 & Bitboard::FULL.0)
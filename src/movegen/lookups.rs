@@ -17,7 +17,86 @@ pub const fn get_gold_attacks_internal(sq: Square, stm: u8) -> Bitboard {
     Bitboard(GOLD_ATTACKS[stm as usize][sq.as_usize()])
 }
 
+pub const fn get_between_internal(a: Square, b: Square) -> Bitboard {
+    Bitboard(BETWEEN[a.as_usize()][b.as_usize()])
+}
+
+pub const fn get_line_internal(a: Square, b: Square) -> Bitboard {
+    Bitboard(LINE[a.as_usize()][b.as_usize()])
+}
+
 // the lookups themselves
+// the whole rank, file or diagonal through two squares, empty if they don't share one
+static LINE: [[u128; 81]; 81] = {
+    // one direction out of each opposite pair, the other half of the line comes from stepping backwards
+    let directions: [(i8, i8); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+    let mut result = [[0; 81]; 81];
+    let mut from = 0;
+    while from < 81 {
+        let mut dir = 0;
+        while dir < 4 {
+            let (rank_step, file_step) = directions[dir];
+            let mut line = 1 << from;
+            let mut sign = -1;
+            while sign <= 1 {
+                let mut rank = (from / 9) as i8 + sign * rank_step;
+                let mut file = (from % 9) as i8 + sign * file_step;
+                while rank >= 0 && rank < 9 && file >= 0 && file < 9 {
+                    line |= 1 << (rank * 9 + file);
+                    rank += sign * rank_step;
+                    file += sign * file_step;
+                }
+                sign += 2;
+            }
+            let mut to = 0;
+            while to < 81 {
+                if to != from && line & (1 << to) != 0 {
+                    result[from][to] = line;
+                }
+                to += 1;
+            }
+            dir += 1;
+        }
+        from += 1;
+    }
+    result
+};
+
+// squares strictly between two squares sharing a rank, file or diagonal, empty otherwise
+static BETWEEN: [[u128; 81]; 81] = {
+    let directions: [(i8, i8); 8] = [
+        (1, 0),
+        (-1, 0),
+        (0, 1),
+        (0, -1),
+        (1, 1),
+        (1, -1),
+        (-1, 1),
+        (-1, -1),
+    ];
+    let mut result = [[0; 81]; 81];
+    let mut from = 0;
+    while from < 81 {
+        let mut dir = 0;
+        while dir < 8 {
+            let (rank_step, file_step) = directions[dir];
+            let mut rank = (from / 9) as i8 + rank_step;
+            let mut file = (from % 9) as i8 + file_step;
+            let mut ray = 0;
+            while rank >= 0 && rank < 9 && file >= 0 && file < 9 {
+                let to = (rank * 9 + file) as usize;
+                result[from][to] = ray;
+                ray |= 1 << to;
+                rank += rank_step;
+                file += file_step;
+            }
+            dir += 1;
+        }
+        from += 1;
+    }
+    result
+};
+
 const KING_ATTACKS: [u128; 81] = [
     1538,
     3589,
@@ -43,15 +43,15 @@ pub fn get_bishop_attacks_internal(sq: Square, occ: Bitboard) -> Bitboard {
 }
 const LANCE_MAGICS: [[u128; 81]; 2] = [
     [
-        10194502017933093372238,
-        443963134339884184502320,
-        689837006778911441289472,
-        152028944172202605019158,
-        454573026427370909892608,
-        607137707067574884237320,
-        399216544945746487603202,
-        19498535077010928304129,
-        94596061055462287360000,
+        302381418024679751487498,
+        37835426143133130301697,
+        151125241308243729320960,
+        19184623407082172845058,
+        604536985190044928050464,
+        1814583804689998493782016,
+        609204912549777332240512,
+        1199119713267137650816,
+        1888947176382163113476224,
         606910004016578341898562,
         302707324521735665365025,
         229196798312055407642241,
@@ -198,15 +198,15 @@ const LANCE_MAGICS: [[u128; 81]; 2] = [
         2224836439662519141992528,
         8878324253379244326976,
         691540769717509873308261,
-        1596164485324788444247296,
-        1383654574095940169633024,
-        1058991117820135613937795,
+        38092961128543022088192,
+        151743790801107514573056,
+        2438429580519541841960,
         369007293699387818552,
-        85592933597805663521537,
-        168824709884242795497472,
-        2213612262340230783536,
-        40140130344035600699852,
-        75705463886480347766849,
+        2582562202585183289474,
+        623546075564127029374978,
+        78011353062263742300192,
+        1246743968893518817018112,
+        1237287693132111305516288,
     ],
 ];
 const ROOK_MAGICS: [u128; 81] = [
@@ -8,8 +8,8 @@ mod pext;
 
 // when doing pext and magic i'll use #[cfg()] and then #[not(cfg())], if feature pext, use pext, if not, use magic
 use lookups::{
-    get_gold_attacks_internal, get_king_attacks_internal, get_knight_attacks_internal,
-    get_silver_attacks_internal,
+    get_between_internal, get_gold_attacks_internal, get_king_attacks_internal,
+    get_knight_attacks_internal, get_line_internal, get_silver_attacks_internal,
 };
 #[cfg(not(feature = "pext"))]
 use magic::{get_bishop_attacks_internal, get_lance_attacks_internal, get_rook_attacks_internal};
@@ -46,10 +46,227 @@ pub const fn get_gold_attacks(sq: Square, stm: u8) -> Bitboard {
     get_gold_attacks_internal(sq, stm)
 }
 
+// the squares strictly between a and b, empty if they don't share a rank, file or diagonal
+pub const fn between(a: Square, b: Square) -> Bitboard {
+    get_between_internal(a, b)
+}
+
+// the full line through a and b, empty if they don't share a rank, file or diagonal
+pub const fn line(a: Square, b: Square) -> Bitboard {
+    get_line_internal(a, b)
+}
+
 pub fn setwise_pawns(our_pawns: Bitboard, stm: u8) -> Bitboard {
     if stm == 0 {
-        our_pawns << 9
+        our_pawns.shift_north()
     } else {
-        our_pawns >> 9
+        our_pawns.shift_south()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::Board, types::square::NUM_SQUARES, zobrist::next_key};
+
+    fn sq(usi: &str) -> Square {
+        Square::from_usi(usi).expect("bad square")
+    }
+
+    fn bb(squares: &[&str]) -> Bitboard {
+        squares.iter().map(|usi| sq(usi)).collect()
+    }
+
+    // one step at a time along each direction until the edge or a blocker, which is included
+    fn walk(from: Square, occ: Bitboard, directions: &[(i8, i8)]) -> Bitboard {
+        let mut attacks = Bitboard::EMPTY;
+        for &(df, dr) in directions {
+            let (mut file, mut rank) = (from.file() as i8, from.rank() as i8);
+            loop {
+                file += df;
+                rank += dr;
+                if !(0..9).contains(&file) || !(0..9).contains(&rank) {
+                    break;
+                }
+                let to = Square::from_file_rank(file as u8, rank as u8);
+                attacks.set(to);
+                if occ.contains(to) {
+                    break;
+                }
+            }
+        }
+        attacks
+    }
+
+    const ROOK: [(i8, i8); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+    const BISHOP: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+    // every square with 64 occupancies each
+    const TABLE_CASES: u32 = 81 * 64;
+
+    #[test]
+    fn lance_stops_on_the_first_blocker() {
+        let lance = sq("5i");
+        assert_eq!(get_lance_attacks(lance, bb(&["5h"]), 0), bb(&["5h"]));
+        assert_eq!(
+            get_lance_attacks(lance, bb(&["5e", "5c"]), 0),
+            bb(&["5h", "5g", "5f", "5e"])
+        );
+        assert_eq!(
+            get_lance_attacks(lance, bb(&["5a"]), 0),
+            bb(&["5h", "5g", "5f", "5e", "5d", "5c", "5b", "5a"])
+        );
+        // squares behind the lance never block it
+        assert_eq!(
+            get_lance_attacks(sq("5e"), bb(&["5f"]), 0),
+            bb(&["5d", "5c", "5b", "5a"])
+        );
+        assert_eq!(
+            get_lance_attacks(sq("5e"), bb(&["5f", "5g"]), 1),
+            bb(&["5f"])
+        );
+    }
+
+    #[test]
+    fn lance_on_an_edge_file() {
+        let open = Bitboard::EMPTY;
+        assert_eq!(
+            get_lance_attacks(sq("1i"), open, 0),
+            bb(&["1h", "1g", "1f", "1e", "1d", "1c", "1b", "1a"])
+        );
+        assert_eq!(
+            get_lance_attacks(sq("9i"), bb(&["9f"]), 0),
+            bb(&["9h", "9g", "9f"])
+        );
+        assert_eq!(
+            get_lance_attacks(sq("9a"), open, 1),
+            bb(&["9b", "9c", "9d", "9e", "9f", "9g", "9h", "9i"])
+        );
+        assert_eq!(get_lance_attacks(sq("1a"), bb(&["1b"]), 1), bb(&["1b"]));
+        // nothing in front of a lance on its last rank
+        assert_eq!(get_lance_attacks(sq("1a"), open, 0), Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn rook_blocked_on_all_four_rays() {
+        let occ = bb(&["5c", "5g", "3e", "8e"]);
+        assert_eq!(
+            get_rook_attacks(sq("5e"), occ),
+            bb(&["5d", "5c", "5f", "5g", "4e", "3e", "6e", "7e", "8e"])
+        );
+        let touching = bb(&["5d", "5f", "4e", "6e", "5a", "1e"]);
+        assert_eq!(
+            get_rook_attacks(sq("5e"), touching),
+            bb(&["5d", "5f", "4e", "6e"])
+        );
+        assert_eq!(
+            get_rook_attacks(sq("1a"), bb(&["1c", "3a"])),
+            bb(&["1b", "1c", "2a", "3a"])
+        );
+    }
+
+    #[test]
+    fn friendly_blockers_stop_short_and_enemy_ones_get_captured() {
+        let mut board = Board::default();
+        board.load_fen("4k4/9/9/8p/9/P8/9/9/L3K3L b - 1");
+        let targets = |from: &str| -> Bitboard {
+            board
+                .legal_moves_from(sq(from))
+                .iter()
+                .map(|action| action.to())
+                .collect()
+        };
+        assert_eq!(targets("9i"), bb(&["9h", "9g"]));
+        assert_eq!(targets("1i"), bb(&["1h", "1g", "1f", "1e", "1d"]));
+    }
+
+    #[test]
+    fn tables_match_a_ray_walk() {
+        let mut state = 0x626C_6F63_6B65_7273;
+        for i in 0..TABLE_CASES {
+            let from = Square((i % 81) as u8);
+            // sparse and dense occupancies, the piece's own square doesn't matter
+            let occ = match i / 81 {
+                0 => Bitboard::EMPTY,
+                1 => Bitboard::FULL,
+                n if n % 2 == 0 => {
+                    Bitboard(next_key(&mut state) as u128 & next_key(&mut state) as u128)
+                }
+                _ => Bitboard(
+                    (u128::from(next_key(&mut state)) << 64 | u128::from(next_key(&mut state)))
+                        & Bitboard::FULL.0,
+                ),
+            };
+            assert_eq!(
+                get_rook_attacks(from, occ),
+                walk(from, occ, &ROOK),
+                "rook {from}"
+            );
+            assert_eq!(
+                get_bishop_attacks(from, occ),
+                walk(from, occ, &BISHOP),
+                "bishop {from}"
+            );
+            assert_eq!(
+                get_lance_attacks(from, occ, 0),
+                walk(from, occ, &[(0, 1)]),
+                "lance {from}"
+            );
+            assert_eq!(
+                get_lance_attacks(from, occ, 1),
+                walk(from, occ, &[(0, -1)]),
+                "lance {from}"
+            );
+        }
+    }
+
+    #[test]
+    fn between_is_strictly_inside_an_aligned_pair() {
+        assert_eq!(between(sq("5i"), sq("5e")), bb(&["5h", "5g", "5f"]));
+        assert_eq!(between(sq("9c"), sq("6c")), bb(&["8c", "7c"]));
+        assert_eq!(between(sq("1a"), sq("4d")), bb(&["2b", "3c"]));
+        assert_eq!(between(sq("7g"), sq("9e")), bb(&["8f"]));
+        // either way round
+        assert_eq!(between(sq("5e"), sq("5i")), between(sq("5i"), sq("5e")));
+        // neighbours have nothing between them and a knight's jump isn't a line
+        assert!(between(sq("5e"), sq("5f")).is_empty());
+        assert!(between(sq("5e"), sq("4c")).is_empty());
+        assert!(between(sq("9a"), sq("1b")).is_empty());
+    }
+
+    #[test]
+    fn line_runs_edge_to_edge_through_both_squares() {
+        let file: Vec<String> = ('a'..='i').map(|rank| format!("5{rank}")).collect();
+        let file: Vec<&str> = file.iter().map(String::as_str).collect();
+        assert_eq!(line(sq("5i"), sq("5e")), bb(&file));
+        assert_eq!(
+            line(sq("3c"), sq("6f")),
+            bb(&["1a", "2b", "3c", "4d", "5e", "6f", "7g", "8h", "9i"])
+        );
+        assert_eq!(line(sq("2a"), sq("1b")), bb(&["2a", "1b"]));
+        assert!(line(sq("5e"), sq("4c")).is_empty());
+    }
+
+    #[test]
+    fn gote_steps_are_sentes_turned_around() {
+        let sides_swapped = |attacks: Bitboard| attacks.flip_vertical().mirror_horizontal();
+        for sq in (0..NUM_SQUARES).map(Square) {
+            let flipped = sq.flip();
+            assert_eq!(
+                sides_swapped(get_silver_attacks(sq, 0)),
+                get_silver_attacks(flipped, 1)
+            );
+            assert_eq!(
+                sides_swapped(get_gold_attacks(sq, 0)),
+                get_gold_attacks(flipped, 1)
+            );
+            assert_eq!(
+                sides_swapped(get_knight_attacks(sq, 0)),
+                get_knight_attacks(flipped, 1)
+            );
+            assert_eq!(
+                sides_swapped(Bitboard::from_square(sq)),
+                Bitboard::from_square(flipped)
+            );
+        }
     }
 }
@@ -1,19 +1,47 @@
-use std::time::Instant;
+use std::{fs, time::Instant};
 
-use crate::board::Board;
+use crate::{board::Board, types::action::Action};
 
-pub fn split_perft(board: &mut Board, depth: u8) {
-    let actions = board.get_actions();
-    let mut count = 0;
-    let start = Instant::now();
-    for action in &actions {
-        if board.perform_action(*action) {
-            let result = perft_internal(board, depth - 1);
-            println!("{} : {}", action, result);
-            board.undo_action();
-            count += result;
+impl Board {
+    // counts the leaf nodes of the legal move tree at the given depth
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let actions = self.get_actions();
+        let mut count = 0;
+        for action in &actions {
+            if self.perform_action(*action) {
+                count += self.perft(depth - 1);
+                self.unmake_move();
+            }
         }
+        count
     }
+
+    // perft split up by the first move, printing each subtree count as it goes
+    pub fn perft_divide(&mut self, depth: u32) -> Vec<(Action, u64)> {
+        let actions = self.get_actions();
+        let mut results = Vec::new();
+        for action in &actions {
+            if self.perform_action(*action) {
+                let result = self.perft(depth - 1);
+                println!("{} : {}", action, result);
+                self.unmake_move();
+                results.push((*action, result));
+            }
+        }
+        results
+    }
+}
+
+pub fn split_perft(board: &mut Board, depth: u32) {
+    let start = Instant::now();
+    let count: u64 = board
+        .perft_divide(depth)
+        .iter()
+        .map(|(_action, result)| result)
+        .sum();
     println!(
         "{} nodes {} nps",
         count,
@@ -21,9 +49,9 @@ pub fn split_perft(board: &mut Board, depth: u8) {
     );
 }
 
-pub fn perft(board: &mut Board, depth: u8) {
+pub fn perft(board: &mut Board, depth: u32) {
     let start = Instant::now();
-    let result = perft_internal(board, depth);
+    let result = board.perft(depth);
     println!(
         "{} nodes {} nps",
         result,
@@ -31,17 +59,86 @@ pub fn perft(board: &mut Board, depth: u8) {
     );
 }
 
-fn perft_internal(board: &mut Board, depth: u8) -> u64 {
-    if depth == 0 {
-        return 1;
-    }
-    let actions = board.get_actions();
-    let mut count = 0;
-    for action in &actions {
-        if board.perform_action(*action) {
-            count += perft_internal(board, depth - 1);
-            board.undo_action();
+// checks every position in a suite file, one per line as "<sfen> ; D1 <count> ; D2 <count> ...",
+// blank lines and lines starting with # are skipped
+pub fn run_perft_suite(path: &str) -> Result<(), String> {
+    let suite = fs::read_to_string(path).map_err(|err| format!("couldn't read {path}: {err}"))?;
+    let mut board = Board::default();
+    let mut mismatches = Vec::new();
+
+    for (line_number, line) in suite.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad_line = || format!("line {}: couldn't parse \"{line}\"", line_number + 1);
+
+        let mut fields = line.split(';');
+        let sfen = fields.next().ok_or_else(bad_line)?.trim();
+        board
+            .try_load_fen(sfen)
+            .map_err(|err| format!("line {}: invalid sfen: {err:?}", line_number + 1))?;
+
+        for field in fields {
+            let (depth, expected) = field.trim().split_once(' ').ok_or_else(bad_line)?;
+            let depth: u32 = depth
+                .strip_prefix('D')
+                .and_then(|depth| depth.parse().ok())
+                .ok_or_else(bad_line)?;
+            let expected: u64 = expected.trim().parse().map_err(|_| bad_line())?;
+
+            let result = board.perft(depth);
+            if result == expected {
+                println!("{sfen} depth {depth}: {result} ok");
+            } else {
+                println!("{sfen} depth {depth}: {result}, expected {expected}");
+                mismatches.push(format!(
+                    "line {}: depth {depth} gave {result}, expected {expected}",
+                    line_number + 1
+                ));
+            }
         }
     }
-    count
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, STARTPOS_SFEN};
+
+    fn perft_from(sfen: &str, depth: u32) -> u64 {
+        let mut board = Board::default();
+        board.load_fen(sfen);
+        board.perft(depth)
+    }
+
+    #[test]
+    fn startpos_counts() {
+        let counts: Vec<u64> = (1..=3)
+            .map(|depth| perft_from(STARTPOS_SFEN, depth))
+            .collect();
+        assert_eq!(counts, [30, 900, 25470]);
+    }
+
+    #[test]
+    fn drops_and_promotions() {
+        // both hands full and pieces in each promotion zone
+        let sfen = "l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1";
+        assert_eq!(perft_from(sfen, 1), 207);
+        assert_eq!(perft_from(sfen, 2), 28684);
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        let mut board = Board::default();
+        board.load_fen(STARTPOS_SFEN);
+        let divide = board.perft_divide(2);
+        assert_eq!(divide.len(), 30);
+        assert_eq!(divide.iter().map(|(_, count)| count).sum::<u64>(), 900);
+    }
 }
@@ -0,0 +1,742 @@
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::Board,
+    eval::evaluate,
+    tt::{Bound, TranspositionTable},
+    types::{
+        action::{Action, Actionlist},
+        piece::NUM_PIECE_TYPES,
+        square::NUM_SQUARES,
+    },
+};
+
+pub const INFINITY: i32 = 32000;
+pub const MATE_SCORE: i32 = 30000;
+// deepest iterative deepening will go
+pub const MAX_DEPTH: u32 = 64;
+// how far either side of the last score the timed search's window starts
+pub const ASPIRATION_WINDOW: i32 = 50;
+// anything past this is a mate score
+const MATE_BOUND: i32 = MATE_SCORE - 1000;
+// how far from the root killers are kept
+const MAX_PLY: usize = 128;
+// history gets halved once anything reaches this so it stays below the killers
+const HISTORY_MAX: i32 = 1 << 20;
+// longest pv pulled out of the tt after a search
+const MAX_PV_LEN: usize = 32;
+// nodes between looks at the clock
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+// the move ordering tables, node count and pv a search keeps alongside the tt, results only repeat
+// when both start fresh since the tt carries over between searches
+pub struct SearchContext {
+    // two quiet moves per ply that caused a beta cutoff
+    killers: [[Action; 2]; MAX_PLY],
+    // indexed by side, piece type and destination, rewarded for quiet cutoffs
+    history: [[[i32; NUM_SQUARES as usize]; NUM_PIECE_TYPES as usize]; 2],
+    // moves made, quiescence included
+    pub nodes: u64,
+    // from the last fully searched depth
+    pv: Vec<Action>,
+    // when the current iteration has to give up, None for a fixed depth search
+    deadline: Option<Instant>,
+    // set once the deadline passes, every node then unwinds without storing anything
+    stopped: bool,
+    // aspiration windows that had to be widened and searched again
+    researches: u64,
+    // searches moves in the order they were generated, to measure the ordering against
+    #[cfg(test)]
+    unordered: bool,
+}
+
+impl Default for SearchContext {
+    fn default() -> Self {
+        Self {
+            killers: [[Action::default(); 2]; MAX_PLY],
+            history: [[[0; NUM_SQUARES as usize]; NUM_PIECE_TYPES as usize]; 2],
+            nodes: 0,
+            pv: Vec::new(),
+            deadline: None,
+            stopped: false,
+            researches: 0,
+            #[cfg(test)]
+            unordered: false,
+        }
+    }
+}
+
+impl SearchContext {
+    #[must_use]
+    pub fn pv(&self) -> &[Action] {
+        &self.pv
+    }
+
+    // nodes per second over however long the search has been going
+    #[must_use]
+    pub fn nps(&self, elapsed: Duration) -> u64 {
+        (u128::from(self.nodes) * 1_000_000 / elapsed.as_micros().max(1)) as u64
+    }
+
+    // only looks at the clock every so many nodes, once stopped it stays that way for the iteration
+    fn out_of_time(&mut self) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) {
+            self.stopped = self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.stopped
+    }
+
+    // one line per finished depth, with the pv that depth left in the tt
+    fn print_info(&self, depth: u32, score: i32, start: Instant) {
+        let pv: Vec<String> = self.pv.iter().map(Action::to_usi).collect();
+        println!(
+            "info depth {depth} nodes {} nps {} score {} pv {}",
+            self.nodes,
+            self.nps(start.elapsed()),
+            usi_score(score),
+            pv.join(" ")
+        );
+    }
+
+    // tt move, then captures and promotions by mvv-lva, then killers, then the rest by history
+    fn order_moves(
+        &self,
+        board: &Board,
+        actions: &mut Actionlist,
+        tt_move: Option<Action>,
+        ply: usize,
+    ) {
+        #[cfg(test)]
+        if self.unordered {
+            return;
+        }
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        actions.sort_by_cached_key(|action| {
+            let score = if Some(*action) == tt_move {
+                i32::MAX
+            } else if !board.is_quiet(*action) {
+                2 * HISTORY_MAX + 100_000 + board.mvv_lva(*action)
+            } else if *action == killers[0] {
+                2 * HISTORY_MAX + 1
+            } else if *action == killers[1] {
+                2 * HISTORY_MAX
+            } else {
+                let (side, piece, to) = Self::history_index(board, *action);
+                self.history[side][piece][to]
+            };
+            Reverse(score)
+        });
+    }
+
+    // side, piece type and destination of a move
+    fn history_index(board: &Board, action: Action) -> (usize, usize, usize) {
+        let piece = if action.is_drop() {
+            action.piece()
+        } else {
+            board.piece_on_square(action.from())
+        };
+        (
+            board.stm().as_usize(),
+            piece.piece().as_usize(),
+            action.to().as_usize(),
+        )
+    }
+
+    // a quiet move caused a beta cutoff
+    fn reward_quiet(&mut self, board: &Board, action: Action, depth: u32, ply: usize) {
+        if let Some(killers) = self.killers.get_mut(ply) {
+            if killers[0] != action {
+                killers[1] = killers[0];
+                killers[0] = action;
+            }
+        }
+
+        let (side, piece, to) = Self::history_index(board, action);
+        let entry = &mut self.history[side][piece][to];
+        *entry += (depth * depth) as i32;
+        if *entry >= HISTORY_MAX {
+            for value in self.history.iter_mut().flatten().flatten() {
+                *value /= 2;
+            }
+        }
+    }
+}
+
+// fixed depth search, returns the best root move and its score
+pub fn search(
+    board: &mut Board,
+    tt: &mut TranspositionTable,
+    ctx: &mut SearchContext,
+    depth: u32,
+) -> (Action, i32) {
+    let start = Instant::now();
+    let depth = depth.max(1);
+    let best = search_root(board, tt, ctx, depth, -INFINITY, INFINITY, None)
+        .expect("unlimited search can't time out");
+    ctx.pv = extract_pv(board, tt, MAX_PV_LEN);
+    ctx.print_info(depth, best.1, start);
+    best
+}
+
+// iterative deepening until the time runs out, only fully searched depths count
+// each depth after the first starts with a window of the given size either side of the last score,
+// widening whichever side it fails on until the score lands inside, 0 searches the full window
+pub fn search_timed(
+    board: &mut Board,
+    tt: &mut TranspositionTable,
+    ctx: &mut SearchContext,
+    millis: u64,
+    window: i32,
+) -> (Action, i32) {
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(millis);
+    // depth 1 always finishes so there's a move to return
+    let mut best = search_root(board, tt, ctx, 1, -INFINITY, INFINITY, None)
+        .expect("unlimited search can't time out");
+    ctx.pv = extract_pv(board, tt, MAX_PV_LEN);
+    ctx.print_info(1, best.1, start);
+    for depth in 2..=MAX_DEPTH {
+        if Instant::now() >= deadline {
+            break;
+        }
+        let Some(result) = aspiration_search(board, tt, ctx, depth, best.1, window, deadline)
+        else {
+            break;
+        };
+        best = result;
+        // taken now, an unfinished iteration later on can overwrite the root entry
+        ctx.pv = extract_pv(board, tt, MAX_PV_LEN);
+        ctx.print_info(depth, best.1, start);
+    }
+    best
+}
+
+// one depth of the timed search, the window starts either side of the last depth's score and
+// whichever side it fails on gets widened until the score lands inside, None on a timeout
+fn aspiration_search(
+    board: &mut Board,
+    tt: &mut TranspositionTable,
+    ctx: &mut SearchContext,
+    depth: u32,
+    last_score: i32,
+    window: i32,
+    deadline: Instant,
+) -> Option<(Action, i32)> {
+    // mate scores jump around too much between depths for a window to help
+    let mut delta = if last_score.abs() < MATE_BOUND {
+        window
+    } else {
+        0
+    };
+    let (mut alpha, mut beta) = if delta > 0 {
+        (last_score - delta, last_score + delta)
+    } else {
+        (-INFINITY, INFINITY)
+    };
+    loop {
+        let (action, score) = search_root(board, tt, ctx, depth, alpha, beta, Some(deadline))?;
+        if score <= alpha && alpha > -INFINITY {
+            delta *= 2;
+            alpha = (score - delta).max(-INFINITY);
+        } else if score >= beta && beta < INFINITY {
+            delta *= 2;
+            beta = (score + delta).min(INFINITY);
+        } else {
+            return Some((action, score));
+        }
+        ctx.researches += 1;
+    }
+}
+
+// None if the deadline passed before the iteration finished, a score outside the window
+// is only a bound and its move might not be the best
+fn search_root(
+    board: &mut Board,
+    tt: &mut TranspositionTable,
+    ctx: &mut SearchContext,
+    depth: u32,
+    mut alpha: i32,
+    beta: i32,
+    deadline: Option<Instant>,
+) -> Option<(Action, i32)> {
+    ctx.deadline = deadline;
+    ctx.stopped = false;
+    let original_alpha = alpha;
+    let mut best_score = -INFINITY;
+    let mut best_action = Action::default();
+
+    let mut actions = board.get_legal_actions();
+    if actions.is_empty() {
+        return Some((best_action, -MATE_SCORE));
+    }
+    let tt_action = tt.probe(board.hash()).map(|entry| entry.best_action);
+    ctx.order_moves(board, &mut actions, tt_action, 0);
+
+    for action in &actions {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+        board.make_move(*action);
+        ctx.nodes += 1;
+        let score = -negamax(board, tt, ctx, depth - 1, 1, -beta, -alpha);
+        board.unmake_move();
+        if ctx.stopped {
+            return None;
+        }
+
+        if score > best_score {
+            best_score = score;
+            best_action = *action;
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+    }
+
+    let bound = if best_score >= beta {
+        Bound::Lower
+    } else if best_score <= original_alpha {
+        Bound::Upper
+    } else {
+        Bound::Exact
+    };
+    tt.store(board.hash(), best_action, best_score, depth as u8, bound);
+    Some((best_action, best_score))
+}
+
+// follows the tt's best moves from the current position, stopping at a miss, an illegal move or a repetition
+pub fn extract_pv(board: &mut Board, tt: &TranspositionTable, max_len: usize) -> Vec<Action> {
+    let mut pv = Vec::new();
+    while pv.len() < max_len {
+        let Some(entry) = tt.probe(board.hash()) else {
+            break;
+        };
+        // hash collisions can hand us a move from some other position
+        if !board.get_legal_actions().contains(&entry.best_action) {
+            break;
+        }
+        board.make_move(entry.best_action);
+        pv.push(entry.best_action);
+        if board.repetition_count() > 0 {
+            break;
+        }
+    }
+    for _ in &pv {
+        board.unmake_move();
+    }
+    pv
+}
+
+fn negamax(
+    board: &mut Board,
+    tt: &mut TranspositionTable,
+    ctx: &mut SearchContext,
+    depth: u32,
+    ply: i32,
+    mut alpha: i32,
+    beta: i32,
+) -> i32 {
+    // the score doesn't matter, search_root throws the whole iteration away
+    if ctx.out_of_time() {
+        return 0;
+    }
+
+    // a cycle is just sennichite waiting to happen, no point searching it again
+    if board.repetition_count() > 0 {
+        return match board.perpetual_checker() {
+            // perpetual check loses for the side giving it
+            Some(side) if side == board.stm() => -MATE_SCORE + ply,
+            Some(_) => MATE_SCORE - ply,
+            None => 0,
+        };
+    }
+
+    if depth == 0 {
+        return quiesce(board, ctx, alpha, beta);
+    }
+
+    let hash = board.hash();
+    let mut tt_action = None;
+    if let Some(entry) = tt.probe(hash) {
+        tt_action = Some(entry.best_action);
+        if u32::from(entry.depth) >= depth {
+            let score = score_from_tt(entry.score, ply);
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return score,
+                Bound::Upper if score <= alpha => return score,
+                _ => {}
+            }
+        }
+    }
+
+    let mut actions = board.get_legal_actions();
+    // no legal moves is a loss whether or not we're in check, sooner is worse
+    if actions.is_empty() {
+        return -MATE_SCORE + ply;
+    }
+    ctx.order_moves(board, &mut actions, tt_action, ply as usize);
+
+    let original_alpha = alpha;
+    let mut best_score = -INFINITY;
+    let mut best_action = Action::default();
+    for action in &actions {
+        // checks are searched a ply deeper, as long as that can't run past the killer table
+        let extension = u32::from(
+            (ply as usize) + (depth as usize) + 1 < MAX_PLY && board.gives_check(*action),
+        );
+        board.make_move(*action);
+        ctx.nodes += 1;
+        let score = -negamax(
+            board,
+            tt,
+            ctx,
+            depth - 1 + extension,
+            ply + 1,
+            -beta,
+            -alpha,
+        );
+        board.unmake_move();
+        if ctx.stopped {
+            return 0;
+        }
+
+        if score > best_score {
+            best_score = score;
+            best_action = *action;
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    if board.is_quiet(*action) {
+                        ctx.reward_quiet(board, *action, depth, ply as usize);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    let bound = if best_score >= beta {
+        Bound::Lower
+    } else if best_score <= original_alpha {
+        Bound::Upper
+    } else {
+        Bound::Exact
+    };
+    tt.store(
+        hash,
+        best_action,
+        score_to_tt(best_score, ply),
+        depth as u8,
+        bound,
+    );
+
+    best_score
+}
+
+// only looks at captures and promotions so the static eval isn't taken in the middle of an exchange
+fn quiesce(board: &mut Board, ctx: &mut SearchContext, mut alpha: i32, beta: i32) -> i32 {
+    if ctx.out_of_time() {
+        return 0;
+    }
+    let stand_pat = evaluate(board);
+    if stand_pat >= beta {
+        return stand_pat;
+    }
+    if stand_pat > alpha {
+        alpha = stand_pat;
+    }
+
+    let mut captures = board.get_captures();
+    board.sort_captures(&mut captures);
+    for action in &captures {
+        if !board.perform_action(*action) {
+            continue;
+        }
+        ctx.nodes += 1;
+        let score = -quiesce(board, ctx, -beta, -alpha);
+        board.unmake_move();
+        if ctx.stopped {
+            return 0;
+        }
+
+        if score > alpha {
+            alpha = score;
+            if alpha >= beta {
+                break;
+            }
+        }
+    }
+
+    alpha
+}
+
+// usi wants mates as a count of plies, negative when it's the side to move getting mated
+fn usi_score(score: i32) -> String {
+    if score > MATE_BOUND {
+        format!("mate {}", MATE_SCORE - score)
+    } else if score < -MATE_BOUND {
+        format!("mate -{}", MATE_SCORE + score)
+    } else {
+        format!("cp {score}")
+    }
+}
+
+// mate scores are stored relative to the node so they stay correct when found at a different ply
+fn score_to_tt(score: i32, ply: i32) -> i32 {
+    if score > MATE_BOUND {
+        score + ply
+    } else if score < -MATE_BOUND {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: i32) -> i32 {
+    if score > MATE_BOUND {
+        score - ply
+    } else if score < -MATE_BOUND {
+        score + ply
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::STARTPOS_SFEN;
+
+    fn from_sfen(sfen: &str) -> Board {
+        let mut board = Board::default();
+        board.load_fen(sfen);
+        board
+    }
+
+    fn search_depth(board: &mut Board, depth: u32) -> (Action, i32) {
+        let mut tt = TranspositionTable::new(1);
+        search(board, &mut tt, &mut SearchContext::default(), depth)
+    }
+
+    #[test]
+    fn finds_a_mate_in_one() {
+        let mut board = from_sfen("4k4/9/4P4/9/9/9/9/9/4K4 b G 1");
+        let (action, score) = search_depth(&mut board, 3);
+        assert_eq!(action.to_string(), "G*5b");
+        assert_eq!(score, MATE_SCORE - 1);
+
+        // and from the other side once it's been played
+        let mut mated = from_sfen("4k4/4G4/4P4/9/9/9/9/9/4K4 w - 1");
+        assert_eq!(search_depth(&mut mated, 3).1, -MATE_SCORE);
+    }
+
+    #[test]
+    fn quiescence_sees_the_recapture() {
+        // the rook grabs a pawn the gold is guarding, the static eval after it thinks sente is winning
+        let mut board = from_sfen("3k5/9/4g4/4p4/9/4R4/9/9/K8 b - 1");
+        let stand_pat = quiesce(
+            &mut board,
+            &mut SearchContext::default(),
+            -INFINITY,
+            INFINITY,
+        );
+        board.make_move(board.action_from_usi("5f5d").expect("illegal move"));
+        let after_capture = evaluate(&board);
+        let resolved = quiesce(
+            &mut board,
+            &mut SearchContext::default(),
+            -INFINITY,
+            INFINITY,
+        );
+        assert!(
+            resolved > after_capture + 500,
+            "{resolved} vs {after_capture}"
+        );
+        // so grabbing it is worse than not
+        assert!(-resolved < stand_pat);
+    }
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for usi in moves {
+            let action = board.action_from_usi(usi).expect("illegal move");
+            board.make_move(action);
+        }
+    }
+
+    #[test]
+    fn repeating_is_a_draw_but_perpetual_check_loses() {
+        // sente is behind on material so it takes the draw whenever it can
+        let mut board = from_sfen("4k4/9/9/9/9/9/9/9/4K4 w rb2g 1");
+        play(&mut board, &["5a4a", "5i4i", "4a5a"]);
+        let (action, score) = search_depth(&mut board, 2);
+        assert_eq!(action.to_string(), "4i5i");
+        assert_eq!(score, 0);
+
+        // but chasing the king back to where it started with check would lose
+        let mut board = from_sfen("8k/9/9/9/9/9/9/9/4K3R w b2g 1");
+        play(&mut board, &["1a2a", "1i2i", "2a1a"]);
+        let (action, score) = search_depth(&mut board, 2);
+        assert_ne!(action.to_string(), "2i1i");
+        assert!(score > -MATE_BOUND, "{score}");
+    }
+
+    #[test]
+    fn timed_search_keeps_to_its_budget_and_sees_further() {
+        // a knight drop forks the king and rook, depth 1 can't see the rook fall
+        let sfen = "4k1r2/9/9/9/9/9/9/9/4K4 b N 1";
+        let (shallow, shallow_score) = search_depth(&mut from_sfen(sfen), 1);
+        assert_ne!(shallow.to_string(), "N*4c");
+
+        let mut board = from_sfen(sfen);
+        let mut tt = TranspositionTable::new(1);
+        let mut ctx = SearchContext::default();
+        let millis = 300;
+        let start = Instant::now();
+        let (action, score) =
+            search_timed(&mut board, &mut tt, &mut ctx, millis, ASPIRATION_WINDOW);
+        // the clock is only read every so many nodes, so it can run a little over
+        assert!(start.elapsed() < Duration::from_millis(millis + 500));
+        assert_eq!(action.to_string(), "N*4c");
+        assert!(score > shallow_score);
+        assert_eq!(board.to_sfen(), sfen);
+    }
+
+    #[test]
+    fn the_pv_starts_with_the_best_move() {
+        let mut board = from_sfen("4k1r2/9/9/9/9/9/9/9/4K4 b N 1");
+        let mut tt = TranspositionTable::new(1);
+        let mut ctx = SearchContext::default();
+        let (action, _) = search(&mut board, &mut tt, &mut ctx, 4);
+        let pv = extract_pv(&mut board, &tt, 8);
+        assert_eq!(pv.first(), Some(&action));
+        assert_eq!(ctx.pv(), pv);
+        // every move in it is legal in turn and the board comes back untouched
+        for action in &pv {
+            assert!(board.get_legal_actions().contains(action));
+            board.make_move(*action);
+        }
+        for _ in &pv {
+            board.unmake_move();
+        }
+        assert_eq!(board.to_sfen(), "4k1r2/9/9/9/9/9/9/9/4K4 b N 1");
+    }
+
+    #[test]
+    fn ordering_cuts_the_node_count() {
+        for (sfen, depth) in [
+            ("4k1r2/9/9/9/9/9/9/9/4K4 b N 1", 3),
+            ("3k5/9/4g4/4p4/9/4R4/9/9/K8 b - 1", 3),
+        ] {
+            let mut ordered = SearchContext::default();
+            let (_, ordered_score) = search(
+                &mut from_sfen(sfen),
+                &mut TranspositionTable::new(1),
+                &mut ordered,
+                depth,
+            );
+            let mut unordered = SearchContext {
+                unordered: true,
+                ..SearchContext::default()
+            };
+            let (_, unordered_score) = search(
+                &mut from_sfen(sfen),
+                &mut TranspositionTable::new(1),
+                &mut unordered,
+                depth,
+            );
+            assert!(ordered.nodes < unordered.nodes, "{sfen}");
+            assert_eq!(ordered_score, unordered_score, "{sfen}");
+        }
+    }
+
+    fn aspiration_depth(sfen: &str, depth: u32, window: i32) -> ((Action, i32), u64) {
+        let mut board = from_sfen(sfen);
+        let mut tt = TranspositionTable::new(1);
+        let mut ctx = SearchContext::default();
+        let (_, last_score) = search(&mut board, &mut tt, &mut ctx, depth - 1);
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let result = aspiration_search(
+            &mut board, &mut tt, &mut ctx, depth, last_score, window, deadline,
+        )
+        .expect("timed out");
+        (result, ctx.researches)
+    }
+
+    #[test]
+    fn aspiration_windows_widen_until_the_score_fits() {
+        // a quiet position keeps its score between depths and never has to widen
+        let (_, researches) = aspiration_depth(STARTPOS_SFEN, 3, ASPIRATION_WINDOW);
+        assert_eq!(researches, 0);
+
+        // depth 1 misses the knight fork, so depth 2 fails high and has to search again
+        let fork = "4k1r2/9/9/9/9/9/9/9/4K4 b N 1";
+        let (result, researches) = aspiration_depth(fork, 2, ASPIRATION_WINDOW);
+        assert!(researches > 0);
+        let full_window = search_depth(&mut from_sfen(fork), 2);
+        assert_eq!(result, full_window);
+    }
+
+    #[test]
+    fn separate_contexts_agree_on_the_best_move() {
+        let sfen = "4k1r2/9/9/9/9/9/9/9/4K4 b N 1";
+        let board = from_sfen(sfen);
+        let mut first = SearchContext::default();
+        let mut second = SearchContext::default();
+        // each one searches its own clone, the way threads would share the game state
+        let first_best = search(
+            &mut board.clone(),
+            &mut TranspositionTable::new(1),
+            &mut first,
+            4,
+        );
+        let second_best = search(
+            &mut board.clone(),
+            &mut TranspositionTable::new(1),
+            &mut second,
+            4,
+        );
+        assert_eq!(first_best, second_best);
+        assert_eq!(first_best.0.to_string(), "N*4c");
+        assert_eq!(first.nodes, second.nodes);
+        assert_eq!(first.pv(), second.pv());
+        assert_eq!(board.to_sfen(), sfen);
+    }
+
+    #[test]
+    fn nodes_grow_with_depth() {
+        let mut last = 0;
+        for depth in 1..=4 {
+            let mut ctx = SearchContext::default();
+            search(
+                &mut from_sfen(STARTPOS_SFEN),
+                &mut TranspositionTable::new(1),
+                &mut ctx,
+                depth,
+            );
+            assert!(
+                ctx.nodes > last,
+                "depth {depth}: {} after {last}",
+                ctx.nodes
+            );
+            last = ctx.nodes;
+        }
+    }
+
+    #[test]
+    fn mate_scores_are_reported_in_plies() {
+        assert_eq!(usi_score(MATE_SCORE - 1), "mate 1");
+        assert_eq!(usi_score(MATE_SCORE - 3), "mate 3");
+        assert_eq!(usi_score(-(MATE_SCORE - 2)), "mate -2");
+        assert_eq!(usi_score(-150), "cp -150");
+        assert_eq!(usi_score(MATE_BOUND), format!("cp {MATE_BOUND}"));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{board::Board, types::action::Action};
+
+impl Board {
+    // looks for a forced mate where the side to move checks with every move, returns the shortest
+    // mating line within max_depth plies, the defender's moves being the longest resistance found
+    pub fn solve_tsume(&mut self, max_depth: u32) -> Option<Vec<Action>> {
+        self.tsume_attack(max_depth)
+    }
+
+    // or node, deepens until some check mates so the line is the shortest the defence allows,
+    // which is what lets tsume_defend compare true distances
+    fn tsume_attack(&mut self, depth: u32) -> Option<Vec<Action>> {
+        // the attacker makes the last move, so only odd depths can end in mate
+        (1..=depth)
+            .step_by(2)
+            .find_map(|depth| self.tsume_attack_within(depth))
+    }
+
+    // the first checking move that mates within depth plies
+    fn tsume_attack_within(&mut self, depth: u32) -> Option<Vec<Action>> {
+        // pawn drop mates are already filtered out as illegal
+        for action in &self.get_legal_actions() {
+            self.make_move(*action);
+            let line = if self.in_check() {
+                self.tsume_defend(depth - 1)
+            } else {
+                None
+            };
+            self.unmake_move();
+
+            if let Some(mut line) = line {
+                line.insert(0, *action);
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    // and node, every escape has to lead to mate
+    fn tsume_defend(&mut self, depth: u32) -> Option<Vec<Action>> {
+        let escapes = self.get_legal_actions();
+        if escapes.is_empty() {
+            return Some(Vec::new());
+        }
+        if depth == 0 {
+            return None;
+        }
+
+        let mut longest: Option<Vec<Action>> = None;
+        for action in &escapes {
+            self.make_move(*action);
+            let line = self.tsume_attack(depth - 1);
+            self.unmake_move();
+
+            let mut line = line?;
+            line.insert(0, *action);
+            if longest
+                .as_ref()
+                .is_none_or(|longest| line.len() > longest.len())
+            {
+                longest = Some(line);
+            }
+        }
+        longest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+
+    fn solve(sfen: &str, max_depth: u32) -> Option<Vec<String>> {
+        let mut board = Board::default();
+        board.load_fen(sfen);
+        let line = board.solve_tsume(max_depth)?;
+        for action in &line {
+            assert!(board.perform_action(*action), "{action} isn't legal");
+        }
+        assert!(
+            board.in_check() && !board.has_legal_move(),
+            "the line doesn't end in mate"
+        );
+        Some(line.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let line = solve("4k4/9/4P4/9/9/9/9/9/4K4 b G 1", 5);
+        assert_eq!(line, Some(vec!["G*5b".to_owned()]));
+    }
+
+    #[test]
+    fn mate_in_three() {
+        let sfen = "8k/9/6R2/9/9/9/9/9/K8 b G 1";
+        assert_eq!(solve(sfen, 1), None);
+        let line = solve(sfen, 7).expect("no mate found");
+        assert_eq!(line.len(), 3, "{line:?}");
+    }
+
+    #[test]
+    fn pawn_drop_mate_is_not_a_solution() {
+        assert_eq!(solve("kn7/9/1G7/9/9/9/9/9/8K b P 1", 1), None);
+    }
+
+    #[test]
+    fn defender_takes_the_longest_escape() {
+        // 9c8d runs into a mate in one, 9c9b and 9c8b both hold out for three more plies
+        let line = solve("9/6+R2/k8/9/4R4/9/9/9/K2G5 b GS 1", 7).expect("no mate found");
+        assert_eq!(line.len(), 5);
+        assert!(line[1] == "9c9b" || line[1] == "9c8b", "{line:?}");
+    }
+}
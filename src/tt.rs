@@ -0,0 +1,118 @@
+use std::mem::size_of;
+
+use crate::types::action::Action;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Bound {
+    #[default]
+    Exact,
+    // score is at least this (failed high)
+    Lower,
+    // score is at most this (failed low)
+    Upper,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub best_action: Action,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+pub struct TranspositionTable {
+    entries: Vec<Entry>,
+}
+
+impl TranspositionTable {
+    // rounds down to a power of two number of entries so indexing is just a mask
+    pub fn new(mb: usize) -> Self {
+        let max_entries = (mb.max(1) * 1024 * 1024 / size_of::<Entry>()).max(1);
+        let len = if max_entries.is_power_of_two() {
+            max_entries
+        } else {
+            max_entries.next_power_of_two() >> 1
+        };
+        Self {
+            entries: vec![Entry::default(); len],
+        }
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash as usize) & (self.entries.len() - 1)
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<Entry> {
+        let entry = self.entries[self.index(hash)];
+        // the low bits only pick the slot, the full key tells us if it's actually this position
+        if entry.key == hash && entry.depth > 0 {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    pub fn store(&mut self, hash: u64, best_action: Action, score: i32, depth: u8, bound: Bound) {
+        let idx = self.index(hash);
+        self.entries[idx] = Entry {
+            key: hash,
+            best_action,
+            score,
+            depth,
+            bound,
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(Entry::default());
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::square::Square;
+
+    fn action(from: u8, to: u8) -> Action {
+        Action::new_move(Square(from), Square(to), false)
+    }
+
+    #[test]
+    fn stored_entries_come_back() {
+        let mut tt = TranspositionTable::new(1);
+        assert!(tt.len().is_power_of_two());
+        tt.store(0xDEAD_BEEF, action(60, 51), 42, 5, Bound::Lower);
+        let entry = tt.probe(0xDEAD_BEEF).expect("entry lost");
+        assert_eq!(entry.best_action, action(60, 51));
+        assert_eq!(
+            (entry.score, entry.depth, entry.bound),
+            (42, 5, Bound::Lower)
+        );
+        assert_eq!(tt.probe(0xDEAD_BEF0), None);
+    }
+
+    #[test]
+    fn same_slot_different_key_misses() {
+        let mut tt = TranspositionTable::new(1);
+        let hash = 0x1234_5678;
+        // same low bits, so the same slot, but a different position
+        let other = hash + tt.len() as u64;
+        tt.store(hash, action(60, 51), 10, 3, Bound::Exact);
+        assert_eq!(tt.probe(other), None);
+        tt.store(other, action(20, 29), -10, 4, Bound::Upper);
+        assert_eq!(tt.probe(hash), None);
+        assert_eq!(tt.probe(other).map(|entry| entry.score), Some(-10));
+
+        tt.clear();
+        assert_eq!(tt.probe(other), None);
+    }
+}
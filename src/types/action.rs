@@ -3,6 +3,7 @@ use core::fmt;
 use super::{piece::Piece, square::Square};
 use arrayvec::ArrayVec;
 
+// the count lives in the ArrayVec, which already gives len, is_empty, indexing, iter and clear
 pub type Actionlist = ArrayVec<Action, 600>;
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
@@ -34,62 +35,107 @@ impl Action {
         Square(((self.0 >> FROM_OFFSET) & SQUARE_MASK) as u8)
     }
 
+    // from() without the assumption that it's a move, None for drops
+    pub const fn from_square(&self) -> Option<Square> {
+        if self.is_drop() {
+            None
+        } else {
+            Some(self.from())
+        }
+    }
+
     pub const fn piece(&self) -> Piece {
         Piece(((self.0 >> FROM_OFFSET) & SQUARE_MASK) as u8)
     }
 
+    // piece() only means something for drops, None for moves
+    pub const fn dropped_piece(&self) -> Option<Piece> {
+        if self.is_drop() {
+            Some(self.piece())
+        } else {
+            None
+        }
+    }
+
     pub const fn is_promo(&self) -> bool {
         (self.0 & PROMO_MASK) != 0
     }
 
+    pub const fn is_promotion(&self) -> bool {
+        self.is_promo()
+    }
+
     pub const fn is_drop(&self) -> bool {
         (self.0 & DROP_MASK) != 0
     }
 
     pub fn to_usi(&self) -> String {
-        let to_square = self.to();
-        let to_file = 9 - to_square.file();
-        let to_rank = (b'i' - to_square.rank()) as char;
-
         if self.is_drop() {
-            format!("{}*{}{}", self.piece(), to_file, to_rank)
+            // drops are always written with the uppercase letter, whoever is dropping, which is
+            // what the piece shows as with the side stripped off
+            format!("{}*{}", self.piece().piece(), self.to())
+        } else if self.is_promo() {
+            format!("{}{}+", self.from(), self.to())
         } else {
-            let from_square = self.from();
-            let from_file = 9 - from_square.file();
-            let from_rank = (b'i' - from_square.rank()) as char;
-            if self.is_promo() {
-                format!("{}{}{}{}+", from_file, from_rank, to_file, to_rank)
-            } else {
-                format!("{}{}{}{}", from_file, from_rank, to_file, to_rank)
-            }
+            format!("{}{}", self.from(), self.to())
         }
     }
 }
 
 impl fmt::Display for Action {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let to_square = self.to();
-        let to_file = 9 - to_square.file();
-        let to_rank = (b'i' - to_square.rank()) as char;
-
-        let output = if self.is_drop() {
-            format!(
-                "{}*{}{}",
-                self.piece().to_string().to_ascii_uppercase(),
-                to_file,
-                to_rank
-            )
-        } else {
-            let from_square = self.from();
-            let from_file = 9 - from_square.file();
-            let from_rank = (b'i' - from_square.rank()) as char;
-            if self.is_promo() {
-                format!("{}{}{}{}+", from_file, from_rank, to_file, to_rank)
-            } else {
-                format!("{}{}{}{}", from_file, from_rank, to_file, to_rank)
-            }
-        };
-
-        write!(f, "{output}")
+        write!(f, "{}", self.to_usi())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(usi: &str) -> Square {
+        Square::from_usi(usi).expect("bad square")
+    }
+
+    #[test]
+    fn actionlists_keep_their_order() {
+        let actions = [
+            Action::new_move(sq("7g"), sq("7f"), false),
+            Action::new_move(sq("8h"), sq("2b"), true),
+            Action::new_drop(Piece::GOLD, sq("5e")),
+        ];
+        let mut list = Actionlist::new();
+        assert!(list.is_empty());
+        for action in actions {
+            list.push(action);
+        }
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[1], actions[1]);
+        assert!(list.iter().copied().eq(actions));
+        assert!(list.clone().into_iter().eq(actions));
+        list.clear();
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn constructors_read_back_through_the_accessors() {
+        let quiet = Action::new_move(sq("7g"), sq("7f"), false);
+        assert_eq!(quiet.from_square(), Some(sq("7g")));
+        assert_eq!(quiet.to(), sq("7f"));
+        assert!(!quiet.is_promotion() && !quiet.is_drop());
+        assert_eq!(quiet.dropped_piece(), None);
+        assert_eq!(quiet.to_string(), "7g7f");
+
+        let promotion = Action::new_move(sq("8h"), sq("2b"), true);
+        assert_eq!(promotion.from(), sq("8h"));
+        assert!(promotion.is_promotion());
+        assert_eq!(promotion.to_string(), "8h2b+");
+
+        // gote's drops read back gote's piece but are written the same as sente's
+        let drop = Action::new_drop(Piece::KNIGHT.as_stm(1), sq("1a"));
+        assert_eq!(drop.from_square(), None);
+        assert_eq!(drop.to(), sq("1a"));
+        assert!(drop.is_drop() && !drop.is_promotion());
+        assert_eq!(drop.dropped_piece(), Some(Piece::KNIGHT.as_stm(1)));
+        assert_eq!(drop.to_string(), "N*1a");
     }
 }
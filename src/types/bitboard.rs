@@ -4,7 +4,26 @@ use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, N
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Bitboard(pub u128);
 
-use super::square::{Square, BOARD_LEN};
+use super::{
+    side::Side,
+    square::{Square, BOARD_LEN, NUM_SQUARES},
+};
+
+// as a low and a high u64, a u128 doesn't survive formats like json that go through doubles
+#[cfg(feature = "serde")]
+impl serde::Serialize for Bitboard {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.0 as u64, (self.0 >> 64) as u64).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Bitboard {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (low, high) = <(u64, u64)>::deserialize(deserializer)?;
+        Ok(Self(u128::from(low) | (u128::from(high) << 64)))
+    }
+}
 
 // a mask for a single file on the board
 pub const FILEMASK: u128 =
@@ -15,6 +34,9 @@ pub const RANKMASK: u128 = 0b111111111;
 impl Bitboard {
     pub const EMPTY: Self = Self(0);
     pub const FULL: Self = Self((1 << 81) - 1);
+    // everything but the leftmost or rightmost file, for masking off wraparound
+    const NOT_FIRST_FILE: Self = Self(Self::FULL.0 & !FILEMASK);
+    const NOT_LAST_FILE: Self = Self(Self::FULL.0 & !(FILEMASK << (BOARD_LEN - 1)));
 
     #[must_use]
     pub const fn from_square(sq: Square) -> Self {
@@ -31,6 +53,19 @@ impl Bitboard {
         Self(FILEMASK << file)
     }
 
+    #[must_use]
+    pub const fn contains(&self, sq: Square) -> bool {
+        self.0 & (1 << sq.0) != 0
+    }
+
+    pub fn set(&mut self, sq: Square) {
+        self.0 |= 1 << sq.0;
+    }
+
+    pub fn clear(&mut self, sq: Square) {
+        self.0 &= !(1 << sq.0);
+    }
+
     #[must_use]
     #[allow(clippy::cast_possible_truncation)]
     pub const fn lsb(&self) -> u8 {
@@ -51,6 +86,51 @@ impl Bitboard {
         lsb
     }
 
+    #[must_use]
+    pub const fn lsb_square(&self) -> Square {
+        Square(self.lsb())
+    }
+
+    // lowest and highest set squares, none when empty instead of asserting like lsb and msb
+    #[must_use]
+    pub const fn first_square(&self) -> Option<Square> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.lsb_square())
+        }
+    }
+
+    #[must_use]
+    pub const fn last_square(&self) -> Option<Square> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Square(127 - self.msb()))
+        }
+    }
+
+    pub fn pop_lsb_square(&mut self) -> Square {
+        Square(self.pop_lsb())
+    }
+
+    // msb is a leading zero count, so the square is counted back from the top bit
+    pub fn pop_msb(&mut self) -> u8 {
+        let msb: u8 = 127 - self.msb();
+        self.0 ^= 1 << msb;
+        msb
+    }
+
+    #[must_use]
+    pub fn squares(&self) -> Vec<Square> {
+        self.into_iter().collect()
+    }
+
+    #[must_use]
+    pub fn iter_rev(self) -> RevBiterator {
+        RevBiterator { board: self }
+    }
+
     #[must_use]
     pub const fn popcount(&self) -> u32 {
         self.0.count_ones()
@@ -104,10 +184,177 @@ impl Bitboard {
         board & Self::FULL
     }
 
+    // north is towards gote's side of the board, east towards the higher file index
+    #[must_use]
+    pub const fn shift_north(&self) -> Bitboard {
+        Bitboard((self.0 << BOARD_LEN) & Self::FULL.0)
+    }
+
+    #[must_use]
+    pub const fn shift_south(&self) -> Bitboard {
+        Bitboard(self.0 >> BOARD_LEN)
+    }
+
+    #[must_use]
+    pub const fn shift_east(&self) -> Bitboard {
+        Bitboard((self.0 & Self::NOT_LAST_FILE.0) << 1)
+    }
+
+    #[must_use]
+    pub const fn shift_west(&self) -> Bitboard {
+        Bitboard((self.0 & Self::NOT_FIRST_FILE.0) >> 1)
+    }
+
+    #[must_use]
+    pub const fn shift_north_east(&self) -> Bitboard {
+        Bitboard(((self.0 & Self::NOT_LAST_FILE.0) << (BOARD_LEN + 1)) & Self::FULL.0)
+    }
+
+    #[must_use]
+    pub const fn shift_north_west(&self) -> Bitboard {
+        Bitboard(((self.0 & Self::NOT_FIRST_FILE.0) << (BOARD_LEN - 1)) & Self::FULL.0)
+    }
+
+    #[must_use]
+    pub const fn shift_south_east(&self) -> Bitboard {
+        Bitboard((self.0 & Self::NOT_LAST_FILE.0) >> (BOARD_LEN - 1))
+    }
+
+    #[must_use]
+    pub const fn shift_south_west(&self) -> Bitboard {
+        Bitboard((self.0 & Self::NOT_FIRST_FILE.0) >> (BOARD_LEN + 1))
+    }
+
+    // swaps rank 0 with rank 8 and so on, turning one side's view of the board into the other's
+    #[must_use]
+    pub fn flip_vertical(&self) -> Bitboard {
+        let mut res = Bitboard::EMPTY;
+        for rank in 0..BOARD_LEN {
+            let row = (self.0 >> (BOARD_LEN * rank)) & RANKMASK;
+            res.0 |= row << (BOARD_LEN * (BOARD_LEN - 1 - rank));
+        }
+        res
+    }
+
+    // swaps file 0 with file 8 and so on
+    #[must_use]
+    pub fn mirror_horizontal(&self) -> Bitboard {
+        let mut res = Bitboard::EMPTY;
+        for file in 0..BOARD_LEN {
+            let column = (self.0 >> file) & FILEMASK;
+            res.0 |= column << (BOARD_LEN - 1 - file);
+        }
+        res
+    }
+
+    // turns the board around, square 0 swaps with square 80, the same as flipping both ways
+    #[must_use]
+    pub const fn rotate180(&self) -> Bitboard {
+        Bitboard(self.0.reverse_bits() >> (128 - NUM_SQUARES))
+    }
+
+    // shifts by a square offset, left for positive and right for negative, so +9 is a step north
+    // and -1 a step west, the offset is split into ranks and at most 4 files either way and
+    // anything that would wrap around a side edge or go off the board is dropped
+    #[must_use]
+    pub fn shift_signed(&self, delta: i8) -> Bitboard {
+        let files = delta.rem_euclid(BOARD_LEN as i8);
+        let files = if files > 4 {
+            files - BOARD_LEN as i8
+        } else {
+            files
+        };
+        let mut source = *self & Self::FULL;
+        for file in 0..files.unsigned_abs() {
+            source &= if files > 0 {
+                !Self::from_file(BOARD_LEN - 1 - file)
+            } else {
+                !Self::from_file(file)
+            };
+        }
+        let shifted = if delta >= 0 {
+            source << delta.unsigned_abs()
+        } else {
+            source >> delta.unsigned_abs()
+        };
+        shifted & Self::FULL
+    }
+
+    // the squares plus everything a king step away from them, so a king's square dilates to its zone
+    #[must_use]
+    pub fn king_dilate(&self) -> Bitboard {
+        [-10, -9, -8, -1, 1, 8, 9, 10]
+            .into_iter()
+            .fold(*self, |dilated, delta| dilated | self.shift_signed(delta))
+    }
+
+    // the same grid as display, but bordered and labeled with usi files and ranks like print_state
+    #[must_use]
+    pub fn pretty(&self) -> String {
+        let mut res = "   9 8 7 6 5 4 3 2 1\n".to_owned();
+        res += " ┌───────────────────┐\n";
+        for rank in (0..9).rev() {
+            res += " │";
+            for file in 0..9 {
+                if self.contains(Square::from_file_rank(file, rank)) {
+                    res += " 1";
+                } else {
+                    res += " 0";
+                }
+            }
+            res += " │ ";
+            res.push((b'i' - rank) as char);
+            res += "\n";
+        }
+        res += " └───────────────────┘\n";
+        res
+    }
+
     pub fn file_fill(&self) -> Bitboard {
         self.fill_upwards() | self.fill_downwards()
     }
 
+    // every rank with something on it
+    pub fn rank_fill(&self) -> Bitboard {
+        let mut result = Bitboard::EMPTY;
+        for rank in 0..BOARD_LEN {
+            let rank_bb = Bitboard::from_rank(rank);
+            if (*self & rank_bb).is_not_empty() {
+                result |= rank_bb;
+            }
+        }
+        result
+    }
+
+    // the furthest rank forward for a side, sente moves up the board
+    #[must_use]
+    pub const fn last_rank(side: Side) -> Bitboard {
+        if matches!(side, Side::Sente) {
+            Self::from_rank(BOARD_LEN - 1)
+        } else {
+            Self::from_rank(0)
+        }
+    }
+
+    #[must_use]
+    pub const fn last_two_ranks(side: Side) -> Bitboard {
+        if matches!(side, Side::Sente) {
+            Self(Self::from_rank(BOARD_LEN - 1).0 | Self::from_rank(BOARD_LEN - 2).0)
+        } else {
+            Self(Self::from_rank(0).0 | Self::from_rank(1).0)
+        }
+    }
+
+    // the three ranks where a side's pieces can promote
+    #[must_use]
+    pub const fn promotion_zone(side: Side) -> Bitboard {
+        if matches!(side, Side::Sente) {
+            Self(Self::last_two_ranks(Side::Sente).0 | Self::from_rank(BOARD_LEN - 3).0)
+        } else {
+            Self(Self::last_two_ranks(Side::Gote).0 | Self::from_rank(2).0)
+        }
+    }
+
     pub const fn const_and(&self, rhs: Self) -> Bitboard {
         Bitboard(self.0 & rhs.0)
     }
@@ -236,12 +483,18 @@ impl IntoIterator for Bitboard {
     type Item = Square;
     type IntoIter = Biterator;
 
-    #[must_use]
     fn into_iter(self) -> Self::IntoIter {
         Biterator { board: self }
     }
 }
 
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::EMPTY, |board, sq| board | Self::from_square(sq))
+    }
+}
+
 pub struct Biterator {
     board: Bitboard,
 }
@@ -257,3 +510,204 @@ impl Iterator for Biterator {
         }
     }
 }
+
+pub struct RevBiterator {
+    board: Bitboard,
+}
+
+impl Iterator for RevBiterator {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.board.is_empty() {
+            None
+        } else {
+            Some(Square(self.board.pop_msb()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_of(squares: &[u8]) -> Bitboard {
+        squares.iter().map(|&sq| Square(sq)).collect()
+    }
+
+    #[test]
+    fn reverse_iteration_runs_backwards() {
+        let board = board_of(&[0, 3, 40, 63, 64, 80]);
+        let mut forward = board.squares();
+        forward.reverse();
+        assert_eq!(board.iter_rev().collect::<Vec<_>>(), forward);
+        assert_eq!(Bitboard::EMPTY.iter_rev().next(), None);
+    }
+
+    #[test]
+    fn square_pops_agree_with_raw_pops() {
+        let mut raw = board_of(&[2, 17, 70, 80]);
+        let mut squares = raw;
+        while raw.is_not_empty() {
+            assert_eq!(squares.lsb_square(), Square(raw.lsb()));
+            assert_eq!(squares.pop_lsb_square(), Square(raw.pop_lsb()));
+            assert_eq!(squares, raw);
+        }
+    }
+
+    #[test]
+    fn shifts_dont_wrap_around_the_edges() {
+        let first_file = Bitboard::from_file(0);
+        let last_file = Bitboard::from_file(BOARD_LEN - 1);
+        assert!(first_file.shift_west().is_empty());
+        assert!(first_file.shift_north_west().is_empty());
+        assert!(first_file.shift_south_west().is_empty());
+        assert!(last_file.shift_east().is_empty());
+        assert!(last_file.shift_north_east().is_empty());
+        assert!(last_file.shift_south_east().is_empty());
+        assert!(Bitboard::from_rank(BOARD_LEN - 1).shift_north().is_empty());
+        assert!(Bitboard::from_rank(0).shift_south().is_empty());
+
+        let sq = Square::from_file_rank(0, 4);
+        assert_eq!(
+            Bitboard::from_square(sq).shift_east(),
+            Bitboard::from_square(Square::from_file_rank(1, 4))
+        );
+        assert_eq!(
+            Bitboard::from_square(sq).shift_north_east(),
+            Bitboard::from_square(Square::from_file_rank(1, 5))
+        );
+    }
+
+    #[test]
+    fn pretty_labels_line_up_with_usi() {
+        let sq = Square::from_usi("7f").expect("bad square");
+        let pretty = Bitboard::from_square(sq).pretty();
+        let lines: Vec<&str> = pretty.lines().collect();
+        assert_eq!(lines[0], "   9 8 7 6 5 4 3 2 1");
+        assert_eq!(lines[7], " │ 0 0 1 0 0 0 0 0 0 │ f");
+        assert_eq!(pretty.matches('1').count(), 2);
+    }
+
+    #[test]
+    fn promotion_zones_are_the_far_three_ranks() {
+        let ranks = |ranks: &[u8]| {
+            ranks
+                .iter()
+                .fold(Bitboard::EMPTY, |bb, &rank| bb | Bitboard::from_rank(rank))
+        };
+        // sente moves up the board towards rank 8, which is usi rank a
+        assert_eq!(Bitboard::promotion_zone(Side::Sente), ranks(&[6, 7, 8]));
+        assert_eq!(Bitboard::promotion_zone(Side::Gote), ranks(&[0, 1, 2]));
+        assert_eq!(Bitboard::last_rank(Side::Sente), ranks(&[8]));
+        assert_eq!(Bitboard::last_two_ranks(Side::Gote), ranks(&[0, 1]));
+        assert!(Bitboard::promotion_zone(Side::Sente)
+            .contains(Square::from_usi("3c").expect("bad square")));
+
+        let scattered = board_of(&[0, 40, 41]);
+        assert_eq!(scattered.rank_fill(), ranks(&[0, 4]));
+    }
+
+    #[test]
+    fn collecting_squares_builds_a_board() {
+        let board: Bitboard = [Square(0), Square(10), Square(80)].into_iter().collect();
+        assert_eq!(board.popcount(), 3);
+        assert!(
+            board.contains(Square(0)) && board.contains(Square(10)) && board.contains(Square(80))
+        );
+        assert!(!board.contains(Square(1)));
+        assert_eq!(board.squares(), [Square(0), Square(10), Square(80)]);
+    }
+
+    #[test]
+    fn set_and_clear_round_trip_on_the_corners() {
+        for sq in [Square(0), Square(NUM_SQUARES - 1)] {
+            let mut board = Bitboard::EMPTY;
+            assert!(!board.contains(sq));
+            board.set(sq);
+            assert!(board.contains(sq));
+            assert_eq!(board, Bitboard::from_square(sq));
+            board.set(sq);
+            assert_eq!(board.popcount(), 1);
+            board.clear(sq);
+            assert!(!board.contains(sq));
+            assert!(board.is_empty());
+        }
+    }
+
+    #[test]
+    fn flipping_either_way_twice_is_identity() {
+        let board = board_of(&[0, 1, 13, 40, 71, 80]);
+        assert_eq!(board.flip_vertical().flip_vertical(), board);
+        assert_eq!(board.mirror_horizontal().mirror_horizontal(), board);
+        assert_eq!(board.flip_vertical().mirror_horizontal(), board.rotate180());
+        assert_eq!(
+            Bitboard::from_rank(0).flip_vertical(),
+            Bitboard::from_rank(BOARD_LEN - 1)
+        );
+        assert_eq!(
+            Bitboard::from_file(0).mirror_horizontal(),
+            Bitboard::from_file(BOARD_LEN - 1)
+        );
+    }
+
+    #[test]
+    fn signed_shifts_of_a_full_board_lose_one_rank_or_file() {
+        let full = Bitboard::FULL;
+        assert_eq!(full.shift_signed(9), full ^ Bitboard::from_rank(0));
+        assert_eq!(
+            full.shift_signed(-9),
+            full ^ Bitboard::from_rank(BOARD_LEN - 1)
+        );
+        assert_eq!(full.shift_signed(1), full ^ Bitboard::from_file(0));
+        assert_eq!(
+            full.shift_signed(-1),
+            full ^ Bitboard::from_file(BOARD_LEN - 1)
+        );
+        assert_eq!(full.shift_signed(9), full.shift_north());
+        assert_eq!(full.shift_signed(-10), full.shift_south_west());
+        // a step forward and back again loses nothing away from the edges
+        let pawns = board_of(&[30, 40, 50]);
+        assert_eq!(pawns.shift_signed(9).shift_signed(-9), pawns);
+    }
+
+    #[test]
+    fn first_and_last_squares_handle_empty_boards() {
+        assert_eq!(Bitboard::EMPTY.first_square(), None);
+        assert_eq!(Bitboard::EMPTY.last_square(), None);
+        let board = board_of(&[5, 33, 79]);
+        assert_eq!(board.first_square(), Some(Square(5)));
+        assert_eq!(board.last_square(), Some(Square(79)));
+        let single = board_of(&[80]);
+        assert_eq!(single.first_square(), single.last_square());
+    }
+
+    #[test]
+    fn dilating_adds_the_king_steps_inside_the_board() {
+        let dilated =
+            |usi: &str| board_of(&[Square::from_usi(usi).expect("bad square").0]).king_dilate();
+        assert_eq!(dilated("5e").popcount(), 9);
+        for corner in ["1a", "9a", "1i", "9i"] {
+            assert_eq!(dilated(corner).popcount(), 4, "{corner}");
+        }
+        assert_eq!(dilated("5a").popcount(), 6);
+        assert_eq!(dilated("9e").popcount(), 6);
+
+        let center = Square::from_usi("5e").expect("bad square").0;
+        assert_eq!(
+            dilated("5e"),
+            board_of(&[
+                center - 10,
+                center - 9,
+                center - 8,
+                center - 1,
+                center,
+                center + 1,
+                center + 8,
+                center + 9,
+                center + 10
+            ])
+        );
+        assert_eq!(Bitboard::EMPTY.king_dilate(), Bitboard::EMPTY);
+    }
+}
@@ -1,8 +1,9 @@
 use std::fmt;
 
-use super::piece::Piece;
+use super::{piece::Piece, side::Side};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hand(pub u32);
 
 impl Default for Hand {
@@ -19,7 +20,7 @@ impl Hand {
     const LANCE_BITS: u32 = 3;
     const KNIGHT_BITS: u32 = 3;
     const SILVER_BITS: u32 = 3;
-    const GOLD_BITS: u32 = 2;
+    const GOLD_BITS: u32 = 3;
     const BISHOP_BITS: u32 = 2;
     const ROOK_BITS: u32 = 2;
 
@@ -32,24 +33,24 @@ impl Hand {
     const BISHOP_OFFSET: u32 = Self::ROOK_OFFSET + Self::ROOK_BITS;
     const ROOK_OFFSET: u32 = Self::PAWN_OFFSET + Self::PAWN_BITS;
 
-    // arrays
+    // arrays, indexed by piece type so they have to follow the order of the Piece constants
     const OFFSETS: [u32; 7] = [
         Self::PAWN_OFFSET,
         Self::LANCE_OFFSET,
         Self::KNIGHT_OFFSET,
         Self::SILVER_OFFSET,
-        Self::GOLD_OFFSET,
         Self::BISHOP_OFFSET,
         Self::ROOK_OFFSET,
+        Self::GOLD_OFFSET,
     ];
     const BITS: [u32; 7] = [
         Self::PAWN_BITS,
         Self::LANCE_BITS,
         Self::KNIGHT_BITS,
         Self::SILVER_BITS,
-        Self::GOLD_BITS,
         Self::BISHOP_BITS,
         Self::ROOK_BITS,
+        Self::GOLD_BITS,
     ];
     const MASKS: [u32; 7] = {
         let mut result = [0; 7];
@@ -61,12 +62,28 @@ impl Hand {
         result
     };
 
+    // the most of a piece type a hand can hold
+    pub const fn max_count(piece: Piece) -> u32 {
+        (1 << Self::BITS[piece.piece().as_usize()]) - 1
+    }
+
     // current number
     pub fn num(&self, piece: Piece) -> u8 {
         let piece_type = piece.piece().as_usize();
         ((self.0 & Self::MASKS[piece_type]) >> Self::OFFSETS[piece_type]) as u8
     }
 
+    pub fn count(&self, piece: Piece) -> u8 {
+        self.num(piece)
+    }
+
+    // number of pieces held across every type
+    pub fn total(&self) -> u32 {
+        self.into_iter()
+            .map(|(_piece, count)| u32::from(count))
+            .sum()
+    }
+
     // increase value by 1
     pub fn inc(&mut self, piece: Piece) {
         self.set(piece, self.num(piece) as u32 + 1);
@@ -77,6 +94,20 @@ impl Hand {
         self.set(piece, self.num(piece) as u32 - 1);
     }
 
+    // promoted pieces go back to their base type, capturing a tokin gives a pawn
+    pub fn add(&mut self, piece: Piece) {
+        self.inc(piece.piece().unpromote());
+    }
+
+    pub fn remove(&mut self, piece: Piece) {
+        let piece = piece.piece().unpromote();
+        debug_assert!(
+            self.num(piece) > 0,
+            "removing {piece} from a hand without one"
+        );
+        self.dec(piece);
+    }
+
     // set value to something
     pub fn set(&mut self, piece: Piece, new_count: u32) {
         let piece_type = piece.piece().as_usize();
@@ -86,6 +117,42 @@ impl Hand {
     pub fn is_empty(&self) -> bool {
         self.0 == 0
     }
+
+    // every count with the piece letter in the side's case, zeros included
+    pub fn display_for(&self, side: Side) -> String {
+        let mut output = "".to_owned();
+
+        for i in 0..7 {
+            output += format!("{}{} ", self.num(Piece(i)), Piece(i).as_stm(side.as_u8())).as_str()
+        }
+
+        output
+    }
+
+    // the sfen hand for one side, counts before the piece and left out when it's 1, in the
+    // conventional rook, bishop, gold, silver, knight, lance, pawn order, empty for an empty hand
+    pub fn to_sfen(&self, side: Side) -> String {
+        const SFEN_ORDER: [Piece; 7] = [
+            Piece::ROOK,
+            Piece::BISHOP,
+            Piece::GOLD,
+            Piece::SILVER,
+            Piece::KNIGHT,
+            Piece::LANCE,
+            Piece::PAWN,
+        ];
+        let mut sfen = String::new();
+        for piece in SFEN_ORDER {
+            let count = self.num(piece);
+            if count > 1 {
+                sfen += &count.to_string();
+            }
+            if count > 0 {
+                sfen += &piece.as_stm(side.as_u8()).to_string();
+            }
+        }
+        sfen
+    }
 }
 
 impl IntoIterator for Hand {
@@ -106,15 +173,10 @@ pub struct HandIter {
 }
 
 // ignores capitalisation for now
+// sente's letters, use display_for to get a gote hand in lowercase
 impl fmt::Display for Hand {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut output = "".to_owned();
-
-        for i in 0..7 {
-            output += format!("{}{} ", self.num(Piece(i)), Piece(i)).as_str()
-        }
-
-        write!(f, "{output}")
+        write!(f, "{}", self.display_for(Side::Sente))
     }
 }
 
@@ -134,3 +196,59 @@ impl Iterator for HandIter {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_read_back_what_was_set() {
+        let mut hand = Hand::EMPTY;
+        assert!(hand.is_empty());
+        hand.set(Piece::PAWN, 3);
+        assert_eq!(hand.count(Piece::PAWN), 3);
+        assert_eq!(hand.total(), 3);
+        hand.set(Piece::ROOK, 2);
+        hand.set(Piece::GOLD, 1);
+        assert_eq!(hand.count(Piece::ROOK), 2);
+        assert_eq!(hand.count(Piece::LANCE), 0);
+        assert_eq!(hand.total(), 6);
+        assert!(!hand.is_empty());
+    }
+
+    #[test]
+    fn adding_and_removing_cancel_out() {
+        let mut hand = Hand::EMPTY;
+        hand.set(Piece::SILVER, 1);
+        let before = hand;
+        hand.add(Piece::SILVER);
+        assert_eq!(hand.count(Piece::SILVER), 2);
+        hand.remove(Piece::SILVER);
+        assert_eq!(hand, before);
+    }
+
+    #[test]
+    fn promoted_pieces_go_in_as_their_base_type() {
+        let mut hand = Hand::EMPTY;
+        hand.add(Piece::PROMO_PAWN);
+        hand.add(Piece::PROMO_ROOK.as_stm(1));
+        assert_eq!(hand.count(Piece::PAWN), 1);
+        assert_eq!(hand.count(Piece::ROOK), 1);
+        assert_eq!(hand.total(), 2);
+        hand.remove(Piece::PROMO_PAWN);
+        assert_eq!(hand.count(Piece::PAWN), 0);
+    }
+
+    #[test]
+    fn sfen_hands_use_the_conventional_order() {
+        let mut hand = Hand::EMPTY;
+        hand.set(Piece::PAWN, 3);
+        hand.set(Piece::GOLD, 2);
+        hand.set(Piece::ROOK, 1);
+        assert_eq!(hand.to_sfen(Side::Sente), "R2G3P");
+        assert_eq!(hand.to_sfen(Side::Gote), "r2g3p");
+        hand.set(Piece::PAWN, 18);
+        assert_eq!(hand.to_sfen(Side::Sente), "R2G18P");
+        assert_eq!(Hand::EMPTY.to_sfen(Side::Sente), "");
+    }
+}
@@ -2,4 +2,5 @@ pub mod action;
 pub mod bitboard;
 pub mod hand;
 pub mod piece;
+pub mod side;
 pub mod square;
@@ -2,6 +2,7 @@ use core::panic;
 use std::fmt;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece(pub u8);
 
 //                            real  promoted
@@ -81,7 +82,7 @@ impl fmt::Display for Piece {
             Self::PROMO_PAWN => "+p",
             Self::PROMO_LANCE => "+l",
             Self::PROMO_KNIGHT => "+n",
-            Self::PROMO_SILVER => "+p",
+            Self::PROMO_SILVER => "+s",
             Self::PROMO_BISHOP => "+b",
             Self::PROMO_ROOK => "+r",
             Self::NONE => " ",
@@ -101,3 +102,23 @@ impl Default for Piece {
         Self::NONE
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::side::Side;
+
+    #[test]
+    fn display_uses_the_usi_letters_and_case() {
+        let gote = Side::Gote.as_u8();
+        assert_eq!(Piece::PROMO_PAWN.to_string(), "+P");
+        assert_eq!(Piece::PROMO_ROOK.as_stm(gote).to_string(), "+r");
+        assert_eq!(Piece::SILVER.to_string(), "S");
+        assert_eq!(Piece::KING.as_stm(gote).to_string(), "k");
+
+        let letters: String = (Piece::PAWN.0..Piece::NONE.0)
+            .map(|piece| Piece(piece).to_string() + &Piece(piece).as_stm(gote).to_string())
+            .collect();
+        assert_eq!(letters, "PpLlNnSsBbRrGgKk+P+p+L+l+N+n+S+s+B+b+R+r");
+    }
+}
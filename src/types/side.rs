@@ -0,0 +1,69 @@
+use std::fmt;
+
+// sente moves first and up the board, the discriminants match the side bit of a Piece
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Sente = 0,
+    Gote = 1,
+}
+
+impl Side {
+    pub const ALL: [Side; 2] = [Side::Sente, Side::Gote];
+
+    #[must_use]
+    pub const fn flip(&self) -> Self {
+        match self {
+            Self::Sente => Self::Gote,
+            Self::Gote => Self::Sente,
+        }
+    }
+
+    // anything other than 0 or 1 is a bug
+    #[must_use]
+    pub const fn from_u8(side: u8) -> Self {
+        match side {
+            0 => Self::Sente,
+            1 => Self::Gote,
+            _ => panic!("invalid side"),
+        }
+    }
+
+    #[must_use]
+    pub const fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+
+    #[must_use]
+    pub const fn as_usize(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sente => write!(f, "sente"),
+            Self::Gote => write!(f, "gote"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::piece::Piece;
+
+    #[test]
+    fn sides_flip_and_convert() {
+        assert_eq!(Side::Sente.flip(), Side::Gote);
+        assert_eq!(Side::Gote.flip(), Side::Sente);
+        for side in Side::ALL {
+            assert_eq!(side.flip().flip(), side);
+            assert_eq!(Side::from_u8(side.as_u8()), side);
+            assert_eq!(side.as_usize(), usize::from(side.as_u8()));
+        }
+        // the discriminants are the side bit pieces use
+        assert_eq!(Side::Gote.as_u8(), Piece::GOLD.as_stm(1).side());
+    }
+}
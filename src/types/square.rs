@@ -1,3 +1,4 @@
+use std::fmt;
 use std::ops::AddAssign;
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
@@ -33,6 +34,46 @@ impl Square {
     pub const fn from_rf(rank: u8, file: u8) -> Self {
         Self(rank * BOARD_LEN + file)
     }
+
+    #[must_use]
+    pub const fn from_file_rank(file: u8, rank: u8) -> Self {
+        Self::from_rf(rank, file)
+    }
+
+    // "7f" style usi coordinates
+    #[must_use]
+    pub fn from_usi(s: &str) -> Option<Self> {
+        let &[file, rank] = s.as_bytes() else {
+            return None;
+        };
+        if !(b'1'..=b'9').contains(&file) || !(b'a'..=b'i').contains(&rank) {
+            return None;
+        }
+        Some(Self::from_rf(b'i' - rank, BOARD_LEN - (file - b'0')))
+    }
+
+    #[must_use]
+    pub fn to_usi(self) -> String {
+        self.to_string()
+    }
+
+    // the same square from the other side's point of view
+    #[must_use]
+    pub const fn flip(&self) -> Self {
+        Self(NUM_SQUARES - 1 - self.0)
+    }
+}
+
+// usi coordinates, files count 9..1 from our file 0 and ranks go a..i from the top
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            BOARD_LEN - self.file(),
+            (b'i' - self.rank()) as char
+        )
+    }
 }
 
 impl AddAssign for Square {
@@ -40,3 +81,37 @@ impl AddAssign for Square {
         self.0 += rhs.0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flipping_twice_is_identity() {
+        for sq in (0..NUM_SQUARES).map(Square) {
+            assert_eq!(sq.flip().flip(), sq);
+        }
+        assert_eq!(Square::from_file_rank(0, 0).flip(), Square(NUM_SQUARES - 1));
+    }
+
+    #[test]
+    fn coordinates_match_usi() {
+        // file index 0 is the 9 file and rank 0 is sente's back rank
+        assert_eq!(Square::from_file_rank(0, 0).to_string(), "9i");
+        assert_eq!(Square::from_file_rank(8, 8).to_string(), "1a");
+        assert_eq!(Square::from_file_rank(2, 3).to_string(), "7f");
+        let sq = Square::from_file_rank(2, 3);
+        assert_eq!((sq.file(), sq.rank()), (2, 3));
+        assert_eq!(sq.flip().to_string(), "3d");
+    }
+
+    #[test]
+    fn usi_round_trips_over_every_square() {
+        for sq in (0..NUM_SQUARES).map(Square) {
+            assert_eq!(Square::from_usi(&sq.to_usi()), Some(sq));
+        }
+        for bad in ["0a", "1j", "a1", "10a", "5", ""] {
+            assert_eq!(Square::from_usi(bad), None);
+        }
+    }
+}
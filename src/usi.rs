@@ -1,34 +1,73 @@
 use std::io;
 
 use crate::{
-    board::Board,
-    perft::{perft, split_perft},
-    types::action::Actionlist,
+    board::{Board, STARTPOS_SFEN},
+    perft::{perft, run_perft_suite, split_perft},
+    search::{search, search_timed, SearchContext, ASPIRATION_WINDOW},
+    tt::TranspositionTable,
 };
 
-#[derive(Default)]
+const DEFAULT_HASH_MB: usize = 16;
+const DEFAULT_DEPTH: u32 = 5;
+// time kept back from every timed search for communication lag
+const MOVE_OVERHEAD_MS: u64 = 50;
+
 pub struct UsiManager {
     board: Board,
+    tt: TranspositionTable,
+}
+
+impl Default for UsiManager {
+    fn default() -> Self {
+        Self {
+            board: Board::default(),
+            tt: TranspositionTable::new(DEFAULT_HASH_MB),
+        }
+    }
+}
+
+// reads and answers commands until quit or the end of input
+pub fn run() {
+    let mut manager = UsiManager::default();
+    loop {
+        if !manager.get_command() {
+            break;
+        }
+    }
 }
 
 impl UsiManager {
     pub fn get_command(&mut self) -> bool {
         let mut buffer = String::new();
 
-        io::stdin()
+        let bytes_read = io::stdin()
             .read_line(&mut buffer)
             .expect("failed to read from stdin");
+        // eof
+        if bytes_read == 0 {
+            return false;
+        }
 
-        let (command, _new_line) = buffer.split_at(buffer.len() - 1);
-        self.interpret_command(command)
+        self.interpret_command(buffer.trim_end())
     }
     fn interpret_command(&mut self, command_msg: &str) -> bool {
         let mut command_split = command_msg.split_ascii_whitespace();
         let Some(command) = command_split.next() else {
-            return false;
+            return true;
         };
 
         match command {
+            "usi" => {
+                println!("id name ctenophore");
+                println!("id author Vast342");
+                println!("usiok");
+            }
+            "isready" => println!("readyok"),
+            "usinewgame" => {
+                self.board = Board::default();
+                self.tt.clear();
+            }
+            "go" => self.go(command_msg),
             "perft" => perft(
                 &mut self.board,
                 command_split
@@ -45,6 +84,10 @@ impl UsiManager {
                     .parse()
                     .expect("Invalid Depth"),
             ),
+            "perftsuite" => match run_perft_suite(command_split.next().expect("No Suite")) {
+                Ok(()) => println!("perft suite passed"),
+                Err(err) => println!("perft suite failed:\n{err}"),
+            },
             "position" => self.position(command_msg),
             "print" => self.board.print_state(),
             "quit" => return false,
@@ -53,44 +96,94 @@ impl UsiManager {
         }
         true
     }
+    // position [startpos | sfen <sfen>] [moves <move1> <move2> ...]
     fn position(&mut self, command_msg: &str) {
         let mut command_split = command_msg.split_ascii_whitespace();
         let _first_token = command_split.next().expect("not enough tokens");
         let second_token = command_split.next().expect("not enough tokens");
-        let mut fen: String;
-        if second_token == "startpos" {
-            fen = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1".to_string();
+        let fen = if second_token == "startpos" {
+            // skip over the moves token if there is one
+            command_split.next();
+            STARTPOS_SFEN.to_string()
         } else {
-            let third_token = command_split.next().expect("not enough tokens");
-            fen = third_token.to_owned()
-                + " "
-                + command_split.next().expect("not enough tokens")
-                + " "
-                + command_split.next().expect("not enough tokens");
-            let next_token = command_split.next();
-            if let Some(string) = next_token {
-                if string != "moves" {
-                    fen += string;
-                }
+            command_split
+                .by_ref()
+                .take_while(|token| *token != "moves")
+                .collect::<Vec<&str>>()
+                .join(" ")
+        };
+        if let Err(err) = self.board.try_load_fen(&fen) {
+            println!("Invalid Sfen: {:?}", err);
+            return;
+        }
+
+        for token in command_split {
+            let Some(action) = self.board.action_from_usi(token) else {
+                println!("Invalid Move: {}", token);
+                return;
+            };
+            self.board.make_move(action);
+        }
+    }
+    // go [depth <depth>] [movetime <ms>] [btime <ms>] [wtime <ms>] [byoyomi <ms>], anything else is ignored for now
+    fn go(&mut self, command_msg: &str) {
+        let mut command_split = command_msg.split_ascii_whitespace();
+        let mut depth = None;
+        let mut movetime = None;
+        let mut times = [None; 2];
+        let mut byoyomi = 0;
+        while let Some(token) = command_split.next() {
+            let mut value = || -> u64 {
+                command_split
+                    .next()
+                    .expect("No Value")
+                    .parse()
+                    .expect("Invalid Value")
+            };
+            match token {
+                "depth" => depth = Some(value() as u32),
+                "movetime" => movetime = Some(value()),
+                "btime" => times[0] = Some(value()),
+                "wtime" => times[1] = Some(value()),
+                "byoyomi" => byoyomi = value(),
+                _ => {}
             }
         }
-        self.board = Board::default();
-        self.board.load_fen(&fen);
+
+        if !self.board.has_legal_move() {
+            println!("bestmove resign");
+            return;
+        }
+        // a small slice of what's left on the clock, plus all of the byoyomi minus some overhead
+        let our_time = times[self.board.stm().as_usize()];
+        let millis = movetime.or_else(|| {
+            (our_time.is_some() || byoyomi > 0)
+                .then(|| (our_time.unwrap_or(0) / 30 + byoyomi).saturating_sub(MOVE_OVERHEAD_MS))
+        });
+        let mut ctx = SearchContext::default();
+        let (best_action, _) = match (depth, millis) {
+            (None, Some(millis)) => search_timed(
+                &mut self.board,
+                &mut self.tt,
+                &mut ctx,
+                millis,
+                ASPIRATION_WINDOW,
+            ),
+            (depth, _) => search(
+                &mut self.board,
+                &mut self.tt,
+                &mut ctx,
+                depth.unwrap_or(DEFAULT_DEPTH),
+            ),
+        };
+        println!("bestmove {}", best_action);
     }
     fn make_move(&mut self, command_msg: &str) {
         let mut command_split = command_msg.split_ascii_whitespace();
         let _first_token = command_split.next().expect("not enough tokens");
         let second_token = command_split.next().expect("not enough tokens");
         let index: usize = second_token.parse::<usize>().expect("invalid index");
-        let list = self.board.get_actions();
-        // legality check
-        let mut list2 = Actionlist::new();
-        for action in &list {
-            if self.board.perform_action(*action) {
-                self.board.undo_action();
-                list2.push(*action);
-            }
-        }
-        self.board.perform_action(list2[index]);
+        let list = self.board.get_legal_actions();
+        self.board.make_move(list[index]);
     }
 }
@@ -0,0 +1,79 @@
+use crate::types::{
+    piece::{Piece, NUM_PIECE_TYPES},
+    square::{Square, NUM_SQUARES},
+};
+
+// enough for any count that fits in a hand slot
+const MAX_HAND_COUNT: usize = 32;
+const NUM_HAND_PIECES: usize = 7;
+
+pub struct Zobrist {
+    pieces: [[[u64; NUM_SQUARES as usize]; NUM_PIECE_TYPES as usize]; 2],
+    // one key per held piece, so adding the nth piece of a type toggles hands[side][piece][n - 1]
+    hands: [[[u64; MAX_HAND_COUNT]; NUM_HAND_PIECES]; 2],
+    stm: u64,
+}
+
+pub static ZOBRIST: Zobrist = Zobrist::new();
+
+// splitmix64, good enough for keys and usable in a const context
+pub const fn next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Zobrist {
+    const fn new() -> Self {
+        let mut state: u64 = 0x6374_656E_6F70_686F;
+        let mut pieces = [[[0; NUM_SQUARES as usize]; NUM_PIECE_TYPES as usize]; 2];
+        let mut hands = [[[0; MAX_HAND_COUNT]; NUM_HAND_PIECES]; 2];
+
+        let mut side = 0;
+        while side < 2 {
+            let mut piece = 0;
+            while piece < NUM_PIECE_TYPES as usize {
+                let mut sq = 0;
+                while sq < NUM_SQUARES as usize {
+                    pieces[side][piece][sq] = next_key(&mut state);
+                    sq += 1;
+                }
+                piece += 1;
+            }
+            let mut piece = 0;
+            while piece < NUM_HAND_PIECES {
+                let mut count = 0;
+                while count < MAX_HAND_COUNT {
+                    hands[side][piece][count] = next_key(&mut state);
+                    count += 1;
+                }
+                piece += 1;
+            }
+            side += 1;
+        }
+
+        Self {
+            pieces,
+            hands,
+            stm: next_key(&mut state),
+        }
+    }
+
+    #[must_use]
+    pub const fn piece(&self, piece: Piece, sq: Square) -> u64 {
+        self.pieces[piece.side() as usize][piece.piece().as_usize()][sq.as_usize()]
+    }
+
+    // key for holding the (index + 1)th piece of this type
+    #[must_use]
+    pub const fn hand(&self, side: u8, piece: Piece, index: u8) -> u64 {
+        self.hands[side as usize][piece.piece().as_usize()][index as usize]
+    }
+
+    #[must_use]
+    pub const fn stm(&self) -> u64 {
+        self.stm
+    }
+}
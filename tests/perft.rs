@@ -0,0 +1,13 @@
+use ctenophore::perft::run_perft_suite;
+
+#[test]
+fn perft_suite() {
+    run_perft_suite("tests/perft.epd").unwrap();
+}
+
+// over half a minute in a debug build, run with cargo test --release -- --ignored
+#[test]
+#[ignore]
+fn perft_suite_deep() {
+    run_perft_suite("tests/perft_deep.epd").unwrap();
+}
@@ -0,0 +1,48 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use ctenophore::board::{Board, STARTPOS_SFEN};
+
+// runs the engine on a script and gives back what it printed
+fn run_engine(script: &str) -> String {
+    let mut engine = Command::new(env!("CARGO_BIN_EXE_ctenophore"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("couldn't start the engine");
+    engine
+        .stdin
+        .take()
+        .expect("no stdin")
+        .write_all(script.as_bytes())
+        .expect("couldn't write the script");
+    let output = engine.wait_with_output().expect("engine didn't finish");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).expect("output isn't utf-8")
+}
+
+#[test]
+fn scripted_game_ends_in_a_legal_bestmove() {
+    let output = run_engine(
+        "usi\nisready\nusinewgame\nposition startpos moves 7g7f 3c3d\ngo depth 2\nquit\n",
+    );
+    assert!(output.lines().any(|line| line == "usiok"));
+    assert!(output.lines().any(|line| line == "readyok"));
+
+    let bestmove = output
+        .lines()
+        .find_map(|line| line.strip_prefix("bestmove "))
+        .expect("no bestmove line");
+    let mut board = Board::default();
+    board.load_fen(STARTPOS_SFEN);
+    for usi in ["7g7f", "3c3d"] {
+        let action = board.action_from_usi(usi).expect("illegal move");
+        board.make_move(action);
+    }
+    assert!(
+        board.action_from_usi(bestmove).is_some(),
+        "bestmove {bestmove} isn't legal"
+    );
+}